@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+/// Whether an alias stands for the start of a url, like
+/// `https://git.example.com/` or `git@git.example.com:`, rather than a host.
+fn is_url_base(value: &str) -> bool {
+    value.contains("://") || value.ends_with(':') || value.ends_with('/')
+}
+
+/// The host that an alias stands for, or that its url base points to.
+fn alias_host(value: &str) -> &str {
+    let value = value.split_once("://").map_or(value, |(_, rest)| rest);
+    let value = value.split_once('@').map_or(value, |(_, rest)| rest);
+
+    value.split([':', '/']).next().unwrap_or(value)
+}
+
+/// Expand an alias at the start of `repo`, like git's `url.<base>.insteadOf`.
+///
+/// With `gh = "github.com"`, `gh:owner/name`
+/// becomes `github.com:owner/name`; with `work = "https://git.example.com/"`,
+/// `work:team/name` becomes `https://git.example.com/team/name`.
+#[must_use]
+pub fn expand_alias(repo: &str, aliases: &BTreeMap<String, String>) -> String {
+    let Some((prefix, rest)) = repo.split_once(':') else {
+        return repo.to_string();
+    };
+
+    match aliases.get(prefix) {
+        Some(value) if !rest.starts_with("//") => {
+            if is_url_base(value) {
+                format!("{value}{rest}")
+            } else {
+                format!("{value}:{rest}")
+            }
+        }
+
+        _ => repo.to_string(),
+    }
+}
+
+/// The first alias standing for `host`, if any.
+#[must_use]
+pub fn find_alias<'a>(
+    aliases: &'a BTreeMap<String, String>,
+    host: &str,
+) -> Option<&'a String> {
+    aliases
+        .iter()
+        .find(|(_, value)| alias_host(value).eq_ignore_ascii_case(host))
+        .map(|(alias, _)| alias)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_expands_aliases_to_hosts_and_url_bases() {
+        let aliases = BTreeMap::from([
+            ("gh".to_string(), "github.com".to_string()),
+            ("work".to_string(), "https://git.example.com/".to_string()),
+            ("ssh".to_string(), "git@git.example.com:".to_string()),
+        ]);
+
+        assert_eq!(
+            expand_alias("gh:org/tool", &aliases),
+            "github.com:org/tool"
+        );
+
+        assert_eq!(
+            expand_alias("work:team/service", &aliases),
+            "https://git.example.com/team/service"
+        );
+
+        assert_eq!(
+            expand_alias("ssh:team/service", &aliases),
+            "git@git.example.com:team/service"
+        );
+
+        for repo in ["gitlab.com:org/tool", "https://gh/org/tool", "tool"] {
+            assert_eq!(expand_alias(repo, &aliases), repo);
+        }
+
+        assert_eq!(find_alias(&aliases, "github.com").unwrap(), "gh");
+        assert_eq!(find_alias(&aliases, "git.example.com").unwrap(), "ssh");
+        assert_eq!(find_alias(&aliases, "gitlab.com"), None);
+    }
+}
@@ -0,0 +1,319 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use shellexpand::tilde;
+use walkdir::WalkDir;
+
+use crate::config::Config;
+use crate::error::SrcRepoError;
+use crate::metadata::read_metadata;
+use crate::repo::Repo;
+use crate::tag::has_tag;
+
+const ARCHIVE_EXTENSION: &str = "tar.gz";
+const INFO_EXTENSION: &str = "toml";
+
+/// What is kept next to an archived repository's tarball: who it is, where
+/// it came from, and where it was kept.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ArchiveInfo {
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// The path the repository was archived from, and is restored to
+    pub path: PathBuf,
+
+    /// When it was archived, in seconds since the Unix epoch
+    pub archived: u64,
+}
+
+impl ArchiveInfo {
+    #[must_use]
+    pub fn repo(&self) -> Repo {
+        Repo::new(&self.host, &self.owner, &self.name, None, "")
+    }
+}
+
+/// An archived repository, and where its tarball is.
+#[derive(Clone, Debug)]
+pub struct ArchivedRepo {
+    pub info: ArchiveInfo,
+    pub archive_path: PathBuf,
+}
+
+/// The directory holding archived repositories.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if `archive_directory` is not set and the data
+/// directory can't be determined
+pub fn get_archive_directory(
+    config: &Config,
+) -> Result<PathBuf, SrcRepoError> {
+    let directory = config
+        .archive_directory
+        .as_ref()
+        .ok_or(SrcRepoError::Config)?;
+
+    Ok(PathBuf::from(
+        tilde(&directory.to_string_lossy()).to_string(),
+    ))
+}
+
+/// Archives are kept at `<archive_directory>/<host>/<owner>/<name>.tar.gz`,
+/// with their `ArchiveInfo` in `<name>.toml` next to them.
+#[must_use]
+pub fn get_archive_path(archive_directory: &Path, repo: &Repo) -> PathBuf {
+    archive_directory
+        .join(&repo.host)
+        .join(&repo.owner)
+        .join(format!("{}.{ARCHIVE_EXTENSION}", repo.name))
+}
+
+fn get_info_path(archive_path: &Path) -> PathBuf {
+    let name = archive_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let name = name.strip_suffix(ARCHIVE_EXTENSION).unwrap_or(&name);
+
+    archive_path.with_file_name(format!("{name}{INFO_EXTENSION}"))
+}
+
+fn archive_error(path: &Path) -> SrcRepoError {
+    SrcRepoError::Archive(path.display().to_string())
+}
+
+/// Pack the repository at `path`, working tree and `.git` directory, into a
+/// compressed tarball at `archive_path`, and record `info` next to it.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if the tarball or its info can't be written
+pub fn archive_repo(
+    archive_path: &Path,
+    info: &ArchiveInfo,
+) -> Result<(), SrcRepoError> {
+    let error = |_| archive_error(&info.path);
+
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent).map_err(error)?;
+    }
+
+    // Write to a temporary file first, so that a failure doesn't leave a
+    // partial archive behind.
+    let partial_path = archive_path.with_extension("partial");
+
+    let mut builder = tar::Builder::new(GzEncoder::new(
+        File::create(&partial_path).map_err(error)?,
+        Compression::default(),
+    ));
+
+    builder.follow_symlinks(false);
+
+    let result = builder
+        .append_dir_all(".", &info.path)
+        .and_then(|()| builder.into_inner())
+        .and_then(GzEncoder::finish)
+        .and_then(|_| fs::rename(&partial_path, archive_path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&partial_path);
+    }
+
+    result.map_err(error)?;
+
+    fs::write(
+        get_info_path(archive_path),
+        toml::to_string(info).map_err(|_| archive_error(&info.path))?,
+    )
+    .map_err(error)
+}
+
+/// Unpack the archive at `archive_path` to `target`.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if the archive can't be read or unpacked
+pub fn restore_repo(
+    archive_path: &Path,
+    target: &Path,
+) -> Result<(), SrcRepoError> {
+    let error = |_| archive_error(archive_path);
+
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+
+    name.push(".unarchive");
+
+    let partial_path = target.with_file_name(name);
+
+    fs::create_dir_all(&partial_path).map_err(error)?;
+
+    let mut archive = tar::Archive::new(GzDecoder::new(
+        File::open(archive_path).map_err(error)?,
+    ));
+
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+
+    let result = archive
+        .unpack(&partial_path)
+        .and_then(|()| fs::rename(&partial_path, target));
+
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&partial_path);
+    }
+
+    result.map_err(error)
+}
+
+/// Delete the archive at `archive_path` and its info.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if either can't be removed
+pub fn remove_archive(archive_path: &Path) -> Result<(), SrcRepoError> {
+    let error = |_| archive_error(archive_path);
+
+    fs::remove_file(get_info_path(archive_path)).map_err(error)?;
+    fs::remove_file(archive_path).map_err(error)
+}
+
+/// The info to record for `repo`, archived from `path` now.
+#[must_use]
+pub fn archive_info(repo: &Repo, path: &Path) -> ArchiveInfo {
+    ArchiveInfo {
+        host: repo.host.clone(),
+        owner: repo.owner.clone(),
+        name: repo.name.clone(),
+        url: (!repo.is_local()).then(|| repo.clone().url()),
+        path: path.to_path_buf(),
+        archived: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs()),
+    }
+}
+
+/// The repositories archived in `archive_directory`, in order.
+#[must_use]
+pub fn get_archived_repos(archive_directory: &Path) -> Vec<ArchivedRepo> {
+    let mut archived_repos: Vec<ArchivedRepo> =
+        WalkDir::new(archive_directory)
+            .into_iter()
+            .flatten()
+            .filter(|entry| {
+                entry.file_type().is_file()
+                    && entry
+                        .file_name()
+                        .to_string_lossy()
+                        .ends_with(&format!(".{ARCHIVE_EXTENSION}"))
+            })
+            .filter_map(|entry| {
+                let info =
+                    fs::read_to_string(get_info_path(entry.path())).ok()?;
+
+                Some(ArchivedRepo {
+                    info: toml::from_str(&info).ok()?,
+                    archive_path: entry.path().to_path_buf(),
+                })
+            })
+            .collect();
+
+    archived_repos.sort_by_key(|archived| archived.info.repo().to_string());
+
+    archived_repos
+}
+
+/// Format the archived repositories matching the filters for `list
+/// --archived`, as names or, with `path`, as the paths of their archives.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if the archive directory can't be determined,
+/// or the metadata can't be read when filtering by `tag`
+pub fn list_archived_repos(
+    config: &Config,
+    host: Option<&String>,
+    owner: Option<&String>,
+    name: Option<&String>,
+    tag: Option<&String>,
+    me: bool,
+    no_host: bool,
+    no_owner: bool,
+    path: bool,
+) -> Result<Vec<String>, SrcRepoError> {
+    let owner = if me { config.owner.as_ref() } else { owner };
+    let metadata = tag.map(|_| read_metadata()).transpose()?;
+
+    Ok(get_archived_repos(&get_archive_directory(config)?)
+        .into_iter()
+        .filter(|archived| {
+            let repo = archived.info.repo();
+
+            host.is_none_or(|host| &repo.host == host)
+                && owner.is_none_or(|owner| &repo.owner == owner)
+                && name.is_none_or(|name| &repo.name == name)
+                && tag.zip(metadata.as_ref()).is_none_or(|(tag, metadata)| {
+                    has_tag(config, metadata, &repo, tag)
+                })
+        })
+        .map(|archived| {
+            if path {
+                archived.archive_path.to_string_lossy().to_string()
+            } else {
+                archived.info.repo().display(no_host, no_owner)
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn it_restores_what_it_archives() {
+        let directory =
+            env::temp_dir().join(format!("src-archive-{}", process::id()));
+        let path = directory.join("src/github.com/me/tool");
+
+        fs::create_dir_all(path.join(".git")).unwrap();
+        fs::write(path.join("README.md"), "tool").unwrap();
+        fs::write(path.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+
+        let repo = Repo::new("github.com", "me", "tool", None, "");
+        let archive_path = get_archive_path(&directory.join("archive"), &repo);
+
+        archive_repo(&archive_path, &archive_info(&repo, &path)).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        let archived = get_archived_repos(&directory.join("archive"));
+
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].info.repo(), repo);
+        assert_eq!(archived[0].info.path, path);
+
+        restore_repo(&archive_path, &path).unwrap();
+        remove_archive(&archive_path).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(path.join("README.md")).unwrap(),
+            "tool"
+        );
+        assert!(path.join(".git/HEAD").exists());
+        assert!(get_archived_repos(&directory.join("archive")).is_empty());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
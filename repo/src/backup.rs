@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::{BranchType, Repository};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SrcRepoError;
+use crate::repo::Repo;
+
+const INDEX_FILE_NAME: &str = "index.toml";
+
+/// A repository in a backup: who it is, where its bundle is, and what to set
+/// up again when restoring it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BackupEntry {
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+
+    /// The name of the root the repository was kept in, and is restored to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+
+    /// The bundle, relative to the backup directory, if the repository had
+    /// anything to bundle
+    pub bundle: Option<PathBuf>,
+
+    /// The branch (as `refs/heads/<branch>`) or commit checked out
+    pub head: Option<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remotes: BTreeMap<String, String>,
+
+    /// The upstream of each local branch that has one, as `<remote>/<branch>`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub upstreams: BTreeMap<String, String>,
+}
+
+impl BackupEntry {
+    #[must_use]
+    pub fn repo(&self) -> Repo {
+        Repo::new(&self.host, &self.owner, &self.name, None, "")
+    }
+}
+
+/// The index of a backup directory, listing the repositories bundled in it.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct BackupIndex {
+    #[serde(default)]
+    pub repos: Vec<BackupEntry>,
+}
+
+impl BackupIndex {
+    /// Add `entry`, replacing any earlier entry for the same repository.
+    pub fn insert(&mut self, entry: BackupEntry) {
+        self.repos
+            .retain(|existing| existing.repo() != entry.repo());
+        self.repos.push(entry);
+        self.repos.sort_by_key(|entry| entry.repo().to_string());
+    }
+}
+
+/// Bundles are kept at `<backup_directory>/<host>/<owner>/<name>.bundle`;
+/// this is the part after `<backup_directory>`.
+#[must_use]
+pub fn get_bundle_path(repo: &Repo) -> PathBuf {
+    PathBuf::from(&repo.host)
+        .join(&repo.owner)
+        .join(format!("{}.bundle", repo.name))
+}
+
+fn index_error(directory: &Path) -> SrcRepoError {
+    SrcRepoError::Backup(directory.display().to_string())
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if the index exists but can't be read or
+/// parsed
+pub fn read_backup_index(
+    directory: &Path,
+) -> Result<BackupIndex, SrcRepoError> {
+    let path = directory.join(INDEX_FILE_NAME);
+
+    if !path.exists() {
+        return Ok(BackupIndex::default());
+    }
+
+    toml::from_str(
+        &fs::read_to_string(path).map_err(|_| index_error(directory))?,
+    )
+    .map_err(|_| index_error(directory))
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if the index can't be written
+pub fn write_backup_index(
+    directory: &Path,
+    index: &BackupIndex,
+) -> Result<(), SrcRepoError> {
+    fs::write(
+        directory.join(INDEX_FILE_NAME),
+        toml::to_string(index).map_err(|_| index_error(directory))?,
+    )
+    .map_err(|_| index_error(directory))
+}
+
+/// The index entry for `repo`, read from the repository at `path` in the
+/// root named `root`.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if the repository or its remotes can't be read
+pub fn backup_entry(
+    repo: &Repo,
+    root: Option<&str>,
+    path: &Path,
+) -> Result<BackupEntry, SrcRepoError> {
+    let repository = Repository::open(path)?;
+    let mut remotes = BTreeMap::new();
+
+    for name in repository.remotes()?.iter().flatten() {
+        if let Some(url) = repository.find_remote(name)?.url() {
+            remotes.insert(name.to_string(), url.to_string());
+        }
+    }
+
+    let mut upstreams = BTreeMap::new();
+
+    for (branch, _) in repository.branches(Some(BranchType::Local))?.flatten()
+    {
+        if let (Ok(Some(name)), Ok(upstream)) =
+            (branch.name(), branch.upstream())
+            && let Ok(Some(upstream)) = upstream.name()
+        {
+            upstreams.insert(name.to_string(), upstream.to_string());
+        }
+    }
+
+    let head = repository.find_reference("HEAD").ok().and_then(|head| {
+        head.symbolic_target().map_or_else(
+            || head.target().map(|oid| oid.to_string()),
+            |target| Some(target.to_string()),
+        )
+    });
+
+    Ok(BackupEntry {
+        host: repo.host.clone(),
+        owner: repo.owner.clone(),
+        name: repo.name.clone(),
+        root: root.map(ToString::to_string),
+        bundle: (repository.references()?.count() > 0)
+            .then(|| get_bundle_path(repo)),
+        head,
+        remotes,
+        upstreams,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_one_entry_per_repo() {
+        let entry = |host: &str, head: &str| BackupEntry {
+            host: host.to_string(),
+            owner: "me".to_string(),
+            name: "tool".to_string(),
+            root: None,
+            bundle: Some(get_bundle_path(&Repo::new(
+                host, "me", "tool", None, "",
+            ))),
+            head: Some(head.to_string()),
+            remotes: BTreeMap::new(),
+            upstreams: BTreeMap::new(),
+        };
+
+        let mut index = BackupIndex::default();
+
+        index.insert(entry("gitlab.com", "refs/heads/main"));
+        index.insert(entry("github.com", "refs/heads/main"));
+        index.insert(entry("gitlab.com", "refs/heads/next"));
+
+        let index: BackupIndex =
+            toml::from_str(&toml::to_string(&index).unwrap()).unwrap();
+
+        assert_eq!(index.repos.len(), 2);
+        assert_eq!(index.repos[0].host, "github.com");
+        assert_eq!(index.repos[1].head.as_deref(), Some("refs/heads/next"));
+
+        assert_eq!(
+            index.repos[1].bundle,
+            Some(PathBuf::from("gitlab.com/me/tool.bundle"))
+        );
+    }
+}
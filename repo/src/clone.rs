@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use git2::{Direction, Repository};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SrcRepoError;
+use crate::repo::Repo;
+
+/// The `--filter` specs for partial clones.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CloneFilter {
+    /// Fetch blobs on demand
+    #[serde(rename = "blob:none")]
+    BlobNone,
+
+    /// Fetch trees and blobs on demand
+    #[serde(rename = "tree:0")]
+    TreeZero,
+}
+
+impl CloneFilter {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::BlobNone => "blob:none",
+            Self::TreeZero => "tree:0",
+        }
+    }
+}
+
+/// How to clone a repository, as set under `[clone."<host>"]` or
+/// `[clone."<host>:<owner>/<name>"]` in the config, or by `add` options.
+/// Unset values fall back to the less specific settings.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CloneConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<CloneFilter>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub single_branch: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurse_submodules: Option<bool>,
+}
+
+impl CloneConfig {
+    /// Fill the values unset in `self` from `defaults`.
+    #[must_use]
+    pub fn or(self, defaults: &Self) -> Self {
+        Self {
+            depth: self.depth.or(defaults.depth),
+            filter: self.filter.or(defaults.filter),
+            single_branch: self.single_branch.or(defaults.single_branch),
+            branch: self.branch.or_else(|| defaults.branch.clone()),
+            recurse_submodules: self
+                .recurse_submodules
+                .or(defaults.recurse_submodules),
+        }
+    }
+
+    /// Whether the clone would be missing history or objects that a plain
+    /// `git clone` fetches.
+    #[must_use]
+    pub const fn is_partial(&self) -> bool {
+        self.depth.is_some() || self.filter.is_some()
+    }
+
+    /// The `git clone` arguments for these settings.
+    #[must_use]
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        if let Some(depth) = self.depth {
+            args.push(format!("--depth={depth}"));
+        }
+
+        if let Some(filter) = self.filter {
+            args.push(format!("--filter={}", filter.as_str()));
+        }
+
+        match self.single_branch {
+            Some(true) => args.push("--single-branch".to_string()),
+            Some(false) => args.push("--no-single-branch".to_string()),
+            None => {}
+        }
+
+        if let Some(branch) = &self.branch {
+            args.push(format!("--branch={branch}"));
+        }
+
+        if self.recurse_submodules == Some(true) {
+            args.push("--recurse-submodules".to_string());
+        }
+
+        args
+    }
+}
+
+/// The clone settings for `repo`: `options` first, then the `defaults` for the
+/// repository, then the `defaults` for its host.
+#[must_use]
+pub fn get_clone_config(
+    defaults: &BTreeMap<String, CloneConfig>,
+    repo: &Repo,
+    options: &CloneConfig,
+) -> CloneConfig {
+    [repo.to_string(), repo.host.clone()]
+        .iter()
+        .filter_map(|key| defaults.get(key))
+        .fold(options.clone(), |clone_config, defaults| {
+            clone_config.or(defaults)
+        })
+}
+
+/// What a clone is missing compared to a plain `git clone`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CloneState {
+    /// Made with `--depth`
+    pub shallow: bool,
+
+    /// Made with `--single-branch`, so the remote fetches only one branch
+    pub single_branch: bool,
+
+    /// Made with `--filter`
+    pub partial: bool,
+}
+
+impl CloneState {
+    #[must_use]
+    pub const fn is_full(&self) -> bool {
+        !self.shallow && !self.single_branch && !self.partial
+    }
+}
+
+/// How the repository at `path` was cloned from `remote`.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if `path` is not a git repository or it has no
+/// such remote
+pub fn get_clone_state(
+    path: &Path,
+    remote: &str,
+) -> Result<CloneState, SrcRepoError> {
+    let repository = Repository::open(path)?;
+
+    let single_branch = repository
+        .find_remote(remote)?
+        .refspecs()
+        .filter(|refspec| refspec.direction() == Direction::Fetch)
+        .all(|refspec| !refspec.src().unwrap_or_default().contains('*'));
+
+    let partial = repository
+        .config()?
+        .get_bool(&format!("remote.{remote}.promisor"))
+        .unwrap_or_default();
+
+    Ok(CloneState {
+        shallow: repository.is_shallow(),
+        single_branch,
+        partial,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_prefers_options_then_repo_then_host_settings() {
+        let mut defaults = BTreeMap::new();
+
+        defaults.insert(
+            "github.com".to_string(),
+            CloneConfig {
+                depth: Some(1),
+                single_branch: Some(true),
+                ..CloneConfig::default()
+            },
+        );
+
+        defaults.insert(
+            "github.com:me/monorepo".to_string(),
+            CloneConfig {
+                depth: Some(50),
+                filter: Some(CloneFilter::BlobNone),
+                ..CloneConfig::default()
+            },
+        );
+
+        let repo = Repo::new(
+            "github.com",
+            "me",
+            "monorepo",
+            None,
+            "git@github.com:me/monorepo.git",
+        );
+
+        let options = CloneConfig {
+            filter: Some(CloneFilter::TreeZero),
+            ..CloneConfig::default()
+        };
+
+        assert_eq!(
+            get_clone_config(&defaults, &repo, &options).args(),
+            vec!["--depth=50", "--filter=tree:0", "--single-branch"]
+        );
+    }
+}
@@ -1,20 +1,73 @@
+use std::collections::BTreeMap;
+use std::env;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::OnceLock;
 
-use dirs::{config_dir, home_dir};
+use dirs::{cache_dir, config_dir, data_dir, home_dir};
 use figment::{
     Figment,
     providers::{Env, Format, Serialized, Toml},
+    value::Value,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::clone::CloneConfig;
 use crate::error::SrcRepoError;
+use crate::forge::ForgeConfig;
+use crate::layout::DEFAULT_LAYOUT;
+use crate::log::{LogLevel, log};
+use crate::root::{RootConfig, get_roots};
+
+static SELECTED_PROFILE: OnceLock<String> = OnceLock::new();
 
 #[derive(Deserialize, Serialize)]
 pub struct Config {
+    /// Short prefixes standing for a host or url base, as in `gh:owner/name`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Where `archive` keeps archived repositories
+    pub archive_directory: Option<PathBuf>,
+
+    /// Clone settings by host or `<host>:<owner>/<name>`
+    #[serde(
+        default,
+        rename = "clone",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub clone_defaults: BTreeMap<String, CloneConfig>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub forges: BTreeMap<String, ForgeConfig>,
+
     pub host: Option<String>,
+
+    /// Where repositories are kept under a root
+    pub layout: String,
+
+    pub log_file: Option<PathBuf>,
+
+    #[serde(default)]
+    pub mirror: bool,
+
+    pub mirror_directory: Option<PathBuf>,
     pub owner: Option<String>,
+    pub profile: Option<String>,
+
+    /// The remotes that identify a repository, in order of preference
+    pub remote_names: Vec<String>,
+
     pub root_directory: Option<PathBuf>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub roots: BTreeMap<String, RootConfig>,
+
+    /// Initialize and update submodules in `add` and `sync`
+    pub submodules: bool,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, Vec<String>>,
 }
 
 fn get_git_config_user(host: &str) -> Option<String> {
@@ -41,9 +94,24 @@ impl Default for Config {
             username.map_or_else(|| get_git_config_user("gitlab"), Some);
 
         Self {
+            aliases: BTreeMap::new(),
+            archive_directory: data_dir()
+                .map(|data_dir| data_dir.join("src/archive")),
+            clone_defaults: BTreeMap::new(),
+            forges: BTreeMap::new(),
             root_directory: home_dir().map(|home_dir| home_dir.join("src")),
+            roots: BTreeMap::new(),
             host: Some("github.com".to_string()),
+            layout: DEFAULT_LAYOUT.to_string(),
+            log_file: None,
+            mirror: false,
+            mirror_directory: cache_dir()
+                .map(|cache_dir| cache_dir.join("src/mirrors")),
             owner: username,
+            profile: None,
+            remote_names: vec!["origin".to_string()],
+            submodules: true,
+            tags: BTreeMap::new(),
         }
     }
 }
@@ -60,28 +128,143 @@ pub fn get_config_path() -> Result<String, SrcRepoError> {
         .to_string())
 }
 
-/// # Errors
-///
-/// Will return `SrcRepoError` if it fails to merge configuration from the file
-/// and the environment
-pub fn get_config() -> Result<Config, SrcRepoError> {
-    Figment::from(Serialized::defaults(Config::default()))
-        .merge(Toml::file(get_config_path()?))
+/// Select the profile used by `get_config`, overriding `$SRC_PROFILE`, the
+/// `profile` key and detection from the current directory.
+pub fn select_profile(profile: &str) {
+    let _ = SELECTED_PROFILE.set(profile.to_string());
+}
+
+fn profile_key(profile: &str) -> String {
+    format!("profiles.{profile}")
+}
+
+fn get_config_file() -> Result<Figment, SrcRepoError> {
+    let config_path = get_config_path()?;
+
+    log(
+        &LogLevel::Trace,
+        &format!("reading config from {config_path}"),
+    );
+
+    Ok(Figment::from(Toml::file(config_path)))
+}
+
+/// Merge the defaults, the config file, the profile (if any) and the
+/// environment, in increasing order of precedence. A profile that sets
+/// `root_directory` or `roots` replaces the top-level roots instead of adding
+/// to them.
+fn load(
+    file: &Figment,
+    profile: Option<&str>,
+) -> Result<Config, SrcRepoError> {
+    let mut figment =
+        Figment::from(Serialized::defaults(Config::default())).merge(file);
+    let mut profile_roots = None;
+
+    if let Some(profile) = profile {
+        if !file.contains(&profile_key(profile)) {
+            return Err(SrcRepoError::Profile(profile.to_string()));
+        }
+
+        let profile_file = file.focus(&profile_key(profile));
+
+        if profile_file.contains("root_directory")
+            || profile_file.contains("roots")
+        {
+            profile_roots = Some(
+                profile_file
+                    .extract_inner::<BTreeMap<String, RootConfig>>("roots")
+                    .unwrap_or_default(),
+            );
+        }
+
+        figment = figment.merge(profile_file);
+    }
+
+    let mut config: Config = figment
         .merge(Env::prefixed("SRC_"))
         .extract()
-        .map_or(Err(SrcRepoError::Config), Ok)
+        .map_or(Err(SrcRepoError::Config), Ok)?;
+
+    if let Some(roots) = profile_roots {
+        config.roots = roots;
+    }
+
+    Ok(config)
+}
+
+fn profile_names(file: &Figment) -> Vec<String> {
+    file.extract_inner::<BTreeMap<String, Value>>("profiles")
+        .map(|profiles| profiles.into_keys().collect())
+        .unwrap_or_default()
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to parse the config file path
+pub fn get_profile_names() -> Result<Vec<String>, SrcRepoError> {
+    Ok(profile_names(&get_config_file()?))
+}
+
+/// Find a profile that sets its own root directories and has one containing
+/// the current directory.
+fn detect_profile(file: &Figment) -> Result<Option<String>, SrcRepoError> {
+    let Ok(current_dir) = env::current_dir() else {
+        return Ok(None);
+    };
+
+    for profile in profile_names(file) {
+        let profile_file = file.focus(&profile_key(&profile));
+
+        if !profile_file.contains("root_directory")
+            && !profile_file.contains("roots")
+        {
+            continue;
+        }
+
+        if get_roots(&load(file, Some(&profile))?, None)?
+            .iter()
+            .any(|root| current_dir.starts_with(&root.path))
+        {
+            log(
+                &LogLevel::Debug,
+                &format!(
+                    "using profile {profile:?} for {}",
+                    current_dir.display()
+                ),
+            );
+
+            return Ok(Some(profile));
+        }
+    }
+
+    Ok(None)
 }
 
+/// Load the config, with the active profile (if any) merged over the
+/// top-level values.
+///
 /// # Errors
 ///
 /// Will return `SrcRepoError` if it fails to merge configuration from the file
-/// and the environment
-pub fn get_root_directory() -> Result<String, SrcRepoError> {
-    Ok(get_config()?
-        .root_directory
-        .ok_or(SrcRepoError::Config)?
-        .to_string_lossy()
-        .to_string())
+/// and the environment, or if the selected profile doesn't exist
+pub fn get_config() -> Result<Config, SrcRepoError> {
+    let file = get_config_file()?;
+
+    let profile = match SELECTED_PROFILE.get() {
+        Some(profile) => Some(profile.clone()),
+
+        None => match load(&file, None)?.profile {
+            Some(profile) => Some(profile),
+            None => detect_profile(&file)?,
+        },
+    };
+
+    let mut config = load(&file, profile.as_deref())?;
+
+    config.profile = profile;
+
+    Ok(config)
 }
 
 /// # Errors
@@ -91,3 +274,104 @@ pub fn get_root_directory() -> Result<String, SrcRepoError> {
 pub fn get_username() -> Result<String, SrcRepoError> {
     get_config()?.owner.ok_or(SrcRepoError::Config)
 }
+
+// `Jail` closures return `figment::Error`, which is large.
+#[cfg(test)]
+#[allow(clippy::result_large_err)]
+mod tests {
+    use figment::Jail;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+        owner = "me"
+
+        [roots]
+        personal = "/src"
+        work = "/work"
+
+        [profiles.client]
+        owner = "client-me"
+        roots = { client = "/client" }
+
+        [profiles.gitlab]
+        host = "gitlab.com"
+    "#;
+
+    #[test]
+    fn it_uses_the_top_level_values_without_a_profile() {
+        Jail::expect_with(|_| {
+            let config =
+                load(&Figment::from(Toml::string(CONFIG)), None).unwrap();
+
+            assert_eq!(config.owner.as_deref(), Some("me"));
+            assert_eq!(
+                config.roots.keys().collect::<Vec<_>>(),
+                ["personal", "work"]
+            );
+            assert_eq!(config.host.as_deref(), Some("github.com"));
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn it_selects_the_profile_from_the_environment() {
+        Jail::expect_with(|jail| {
+            jail.create_dir("src")?;
+            jail.create_file("src/config.toml", CONFIG)?;
+            let directory = jail.directory().display().to_string();
+
+            jail.set_env("XDG_CONFIG_HOME", directory);
+            jail.set_env("SRC_PROFILE", "gitlab");
+
+            let config = get_config().unwrap();
+
+            assert_eq!(config.profile.as_deref(), Some("gitlab"));
+            assert_eq!(config.host.as_deref(), Some("gitlab.com"));
+            assert_eq!(config.owner.as_deref(), Some("me"));
+            assert_eq!(config.roots.len(), 2);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn it_replaces_the_roots_with_the_profiles_roots() {
+        Jail::expect_with(|_| {
+            let config =
+                load(&Figment::from(Toml::string(CONFIG)), Some("client"))
+                    .unwrap();
+
+            assert_eq!(config.owner.as_deref(), Some("client-me"));
+            assert_eq!(config.roots.keys().collect::<Vec<_>>(), ["client"]);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn it_detects_the_profile_from_the_current_directory() {
+        Jail::expect_with(|jail| {
+            let directory = jail.directory().join("here");
+
+            jail.create_dir(&directory)?;
+
+            let file = Figment::from(Toml::string(&format!(
+                "{CONFIG}\n[profiles.here]\nroot_directory = \"{}\"\n",
+                directory.display()
+            )));
+
+            assert_eq!(detect_profile(&file).unwrap(), None);
+
+            jail.change_dir("here")?;
+
+            assert_eq!(
+                detect_profile(&file).unwrap().as_deref(),
+                Some("here")
+            );
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+
+use crate::config::Config;
+use crate::list::get_managed_repo_paths;
+use crate::repo::Repo;
+use crate::root::Root;
+use crate::worktree::is_worktrees_path;
+
+/// Something wrong in a managed tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Problem {
+    /// A directory holding files but no repository
+    Stray(PathBuf),
+
+    /// A directory holding nothing, or only empty directories
+    Empty(PathBuf),
+
+    /// A repository that git can't open, or that can't be identified
+    Broken { path: PathBuf, reason: String },
+
+    /// A repository kept somewhere other than where its remote puts it
+    Misplaced {
+        repo: String,
+        path: PathBuf,
+        expected: PathBuf,
+    },
+
+    /// The same repository cloned in several places
+    Duplicate { repo: String, paths: Vec<PathBuf> },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Stray(path) => {
+                write!(f, "{}: not a repository", path.display())
+            }
+
+            Self::Empty(path) => {
+                write!(f, "{}: empty directory", path.display())
+            }
+
+            Self::Broken { path, reason } => {
+                write!(f, "{}: broken repository ({reason})", path.display())
+            }
+
+            Self::Misplaced {
+                repo,
+                path,
+                expected,
+            } => write!(
+                f,
+                "{}: {repo} belongs at {}",
+                path.display(),
+                expected.display()
+            ),
+
+            Self::Duplicate { repo, paths } => {
+                let paths: Vec<String> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+
+                write!(f, "{repo}: cloned at {}", paths.join(", "))
+            }
+        }
+    }
+}
+
+/// Whether a directory holds any repositories, and otherwise any files.
+#[derive(Clone, Copy, Default)]
+struct Contents {
+    repos: bool,
+    files: bool,
+}
+
+/// Find the stray and empty directories below `path`, reporting only the
+/// topmost directory of a tree without repositories.
+fn scan_directory(path: &Path) -> (Contents, Vec<Problem>) {
+    if is_worktrees_path(path) || path.join(".git").exists() {
+        return (
+            Contents {
+                repos: true,
+                files: true,
+            },
+            vec![],
+        );
+    }
+
+    let mut contents = Contents::default();
+    let mut problems = vec![];
+
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(path)
+        .map(|entries| entries.flatten().collect())
+        .unwrap_or_default();
+
+    entries.sort_by_key(fs::DirEntry::path);
+
+    for entry in entries {
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            contents.files = true;
+
+            continue;
+        }
+
+        let (child_contents, child_problems) = scan_directory(&entry.path());
+
+        if child_contents.repos {
+            contents.repos = true;
+            problems.extend(child_problems);
+        } else if child_contents.files {
+            contents.files = true;
+            problems.push(Problem::Stray(entry.path()));
+        } else {
+            problems.push(Problem::Empty(entry.path()));
+        }
+    }
+
+    (contents, problems)
+}
+
+/// Check the repositories in `root`, adding where each one is to `clones`.
+fn check_repos(
+    config: &Config,
+    root: &Root,
+    clones: &mut BTreeMap<String, Vec<PathBuf>>,
+) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    for path in get_managed_repo_paths(&root.path) {
+        let path = PathBuf::from(path);
+
+        if let Err(error) = Repository::open(&path) {
+            problems.push(Problem::Broken {
+                path,
+                reason: error.message().to_string(),
+            });
+
+            continue;
+        }
+
+        match Repo::from(&path.to_string_lossy(), &config.remote_names) {
+            Ok(repo) => {
+                let expected = repo.managed_path(root);
+
+                // If something is already there, it is reported on its own.
+                if expected != path && !expected.exists() {
+                    problems.push(Problem::Misplaced {
+                        repo: repo.to_string(),
+                        path: path.clone(),
+                        expected,
+                    });
+                }
+
+                clones.entry(repo.to_string()).or_default().push(path);
+            }
+
+            Err(error) => problems.push(Problem::Broken {
+                path,
+                reason: error.to_string(),
+            }),
+        }
+    }
+
+    problems
+}
+
+/// Look for problems in the managed trees of `roots`: stray and empty
+/// directories, broken and misplaced repositories, and repositories cloned
+/// more than once.
+#[must_use]
+pub fn audit(config: &Config, roots: &[Root]) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut clones: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for root in roots {
+        problems.extend(scan_directory(Path::new(&root.path)).1);
+        problems.extend(check_repos(config, root, &mut clones));
+    }
+
+    problems.extend(clones.into_iter().filter_map(|(repo, paths)| {
+        (paths.len() > 1).then_some(Problem::Duplicate { repo, paths })
+    }));
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn it_reports_the_top_of_stray_and_empty_trees() {
+        let root =
+            env::temp_dir().join(format!("src-doctor-{}", process::id()));
+
+        for directory in [
+            "github.com/me/repo/.git",
+            "github.com/me/notes/drafts",
+            "github.com/gone/empty",
+            "gitlab.com",
+        ] {
+            fs::create_dir_all(root.join(directory)).unwrap();
+        }
+
+        fs::write(root.join("github.com/me/notes/drafts/todo"), "").unwrap();
+
+        assert_eq!(
+            scan_directory(&root).1,
+            vec![
+                Problem::Empty(root.join("github.com/gone")),
+                Problem::Stray(root.join("github.com/me/notes")),
+                Problem::Empty(root.join("gitlab.com")),
+            ]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -2,12 +2,21 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum SrcRepoError {
+    #[error("failed to archive or restore {0}")]
+    Archive(String),
+
+    #[error("failed to read or write the backup index in {0}")]
+    Backup(String),
+
     #[error("failed to get config")]
     Config,
 
     #[error("")]
     Filter,
 
+    #[error("forge request failed: {0}")]
+    Forge(String),
+
     #[error("failed to read git directory")]
     Git(#[from] git2::Error),
 
@@ -20,6 +29,30 @@ pub enum SrcRepoError {
     #[error("failed to determine home directory")]
     HomeDir,
 
+    #[error("no existing clone or url to import")]
+    Import,
+
+    #[error(
+        "invalid layout {0:?} (use `{{host}}`, `{{host?}}`, `{{owner}}` and \
+         `{{name}}`, ending with `{{name}}`)"
+    )]
+    Layout(String),
+
+    #[error("failed to read or write metadata")]
+    Metadata,
+
+    #[error("profile {0:?} does not exist")]
+    Profile(String),
+
+    #[error("no preferred remote among {0} (see `remote_names`)")]
+    Remote(String),
+
     #[error("invalid characters in repo path")]
     RepoPath,
+
+    #[error("root {0:?} does not exist")]
+    Root(String),
+
+    #[error("unknown forge at {0:?} (set its kind under [forges])")]
+    UnknownForge(String),
 }
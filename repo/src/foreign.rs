@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use dirs::{config_dir, home_dir};
+
+use crate::error::SrcRepoError;
+use crate::list::get_managed_repo_paths;
+
+/// A repository listed by another repository manager: an existing clone, a
+/// url to clone from, or both.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ForeignRepo {
+    pub path: Option<PathBuf>,
+    pub url: Option<String>,
+}
+
+impl ForeignRepo {
+    /// The existing clone, if it is still there.
+    #[must_use]
+    pub fn existing_path(&self) -> Option<&PathBuf> {
+        self.path.as_ref().filter(|path| path.join(".git").exists())
+    }
+}
+
+/// The root directory used by ghq: `ghq root` if ghq is installed, otherwise
+/// `~/ghq`.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to determine the `$HOME` directory
+pub fn get_ghq_root() -> Result<PathBuf, SrcRepoError> {
+    if let Ok(output) = Command::new("ghq").arg("root").output()
+        && output.status.success()
+    {
+        let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        if !root.is_empty() {
+            return Ok(PathBuf::from(root));
+        }
+    }
+
+    Ok(home_dir().ok_or(SrcRepoError::HomeDir)?.join("ghq"))
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to determine the `$HOME` directory
+pub fn get_mrconfig_path() -> Result<PathBuf, SrcRepoError> {
+    Ok(home_dir().ok_or(SrcRepoError::HomeDir)?.join(".mrconfig"))
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to determine the config directory
+pub fn get_gita_path() -> Result<PathBuf, SrcRepoError> {
+    Ok(config_dir()
+        .ok_or(SrcRepoError::Config)?
+        .join("gita/repos.csv"))
+}
+
+/// ghq keeps clones at `<root>/<host>/<owner>/<name>`, like `src`.
+#[must_use]
+pub fn read_ghq(root: &Path) -> Vec<ForeignRepo> {
+    get_managed_repo_paths(&root.to_string_lossy())
+        .into_iter()
+        .map(|path| ForeignRepo {
+            path: Some(PathBuf::from(path)),
+            url: None,
+        })
+        .collect()
+}
+
+/// Find the url in an mr `checkout = git clone <url> [<name>]` command.
+fn parse_mr_checkout(command: &str) -> Option<String> {
+    let mut words = command.split_whitespace();
+
+    if words.next() != Some("git") || words.next() != Some("clone") {
+        return None;
+    }
+
+    words
+        .find(|word| !word.starts_with('-'))
+        .map(|url| url.trim_matches(|c| c == '\'' || c == '"').to_string())
+}
+
+/// Parse the `[<path>]` sections of an mr config file. Paths are relative to
+/// the directory containing the file.
+#[must_use]
+pub fn parse_mrconfig(contents: &str, base: &Path) -> Vec<ForeignRepo> {
+    let mut repos: Vec<ForeignRepo> = vec![];
+
+    for line in contents.lines() {
+        let line = line.trim();
+
+        if let Some(section) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            if section != "DEFAULT" {
+                repos.push(ForeignRepo {
+                    path: Some(base.join(section)),
+                    url: None,
+                });
+            }
+        } else if let Some((key, value)) = line.split_once('=')
+            && key.trim() == "checkout"
+            && let Some(repo) = repos.last_mut()
+        {
+            repo.url = parse_mr_checkout(value.trim());
+        }
+    }
+
+    repos
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to read `path`
+pub fn read_mrconfig(path: &Path) -> Result<Vec<ForeignRepo>, SrcRepoError> {
+    let contents =
+        fs::read_to_string(path).map_err(|_| SrcRepoError::Import)?;
+
+    Ok(parse_mrconfig(
+        &contents,
+        path.parent().unwrap_or_else(|| Path::new("/")),
+    ))
+}
+
+/// Parse gita's `repos.csv`, where the first column is the path of each
+/// repository.
+#[must_use]
+pub fn parse_gita(contents: &str) -> Vec<ForeignRepo> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let path = line.split(',').next()?.trim().trim_matches('"');
+
+            if path.is_empty() {
+                None
+            } else {
+                Some(ForeignRepo {
+                    path: Some(PathBuf::from(path)),
+                    url: None,
+                })
+            }
+        })
+        .collect()
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to read `path`
+pub fn read_gita(path: &Path) -> Result<Vec<ForeignRepo>, SrcRepoError> {
+    Ok(parse_gita(
+        &fs::read_to_string(path).map_err(|_| SrcRepoError::Import)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_mrconfig_sections_and_checkouts() {
+        let contents = "[DEFAULT]\n\
+            jobs = 4\n\
+            \n\
+            [src/tool]\n\
+            checkout = git clone 'git@github.com:our-org/tool.git' 'tool'\n\
+            \n\
+            [notes]\n\
+            update = git pull\n";
+
+        assert_eq!(
+            parse_mrconfig(contents, Path::new("/home/me")),
+            vec![
+                ForeignRepo {
+                    path: Some(PathBuf::from("/home/me/src/tool")),
+                    url: Some("git@github.com:our-org/tool.git".to_string()),
+                },
+                ForeignRepo {
+                    path: Some(PathBuf::from("/home/me/notes")),
+                    url: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_gita_paths() {
+        assert_eq!(
+            parse_gita("/home/me/tool,tool,,\n\n/home/me/notes,notes,,\n"),
+            vec![
+                ForeignRepo {
+                    path: Some(PathBuf::from("/home/me/tool")),
+                    url: None,
+                },
+                ForeignRepo {
+                    path: Some(PathBuf::from("/home/me/notes")),
+                    url: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,832 @@
+use std::env;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::SrcRepoError;
+use crate::log::{LogLevel, log};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Github,
+    Gitlab,
+
+    /// Gitea, or a fork of it like Forgejo
+    Gitea,
+}
+
+/// How to reach the forge at one host. Every field is optional: the kind is
+/// guessed from the host name, the API url from the kind, and the token is
+/// read from the environment.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ForgeConfig {
+    pub kind: Option<ForgeKind>,
+    pub api_url: Option<String>,
+
+    /// Never serialized, so that `src config` doesn't print it
+    #[serde(skip_serializing)]
+    pub token: Option<String>,
+}
+
+/// A repository as listed by a forge.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RemoteRepo {
+    pub owner: String,
+    pub name: String,
+    pub url: String,
+    pub archived: bool,
+    pub fork: bool,
+    pub private: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Visibility {
+    #[default]
+    All,
+    Public,
+    Private,
+}
+
+/// Which of an owner's repositories to keep. Archived repositories and forks
+/// are left out unless asked for.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RemoteRepoFilter {
+    pub archived: bool,
+    pub forks: bool,
+    pub visibility: Visibility,
+}
+
+impl RemoteRepoFilter {
+    #[must_use]
+    pub const fn matches(&self, repo: &RemoteRepo) -> bool {
+        let visible = match self.visibility {
+            Visibility::All => true,
+            Visibility::Public => !repo.private,
+            Visibility::Private => repo.private,
+        };
+
+        if repo.archived && !self.archived {
+            return false;
+        }
+
+        if repo.fork && !self.forks {
+            return false;
+        }
+
+        visible
+    }
+}
+
+pub trait Forge {
+    /// List every repository belonging to `owner`, a user or an organization
+    /// (or group).
+    ///
+    /// # Errors
+    ///
+    /// Will return `SrcRepoError` if a request fails, or if `owner` doesn't
+    /// exist
+    fn list_repos(&self, owner: &str)
+    -> Result<Vec<RemoteRepo>, SrcRepoError>;
+
+    /// The repository that `owner/name` was forked from, or `None` if it is
+    /// not a fork.
+    ///
+    /// # Errors
+    ///
+    /// Will return `SrcRepoError` if a request fails, or if `owner/name`
+    /// doesn't exist
+    fn get_parent(
+        &self,
+        owner: &str,
+        name: &str,
+    ) -> Result<Option<RemoteRepo>, SrcRepoError>;
+}
+
+fn not_found(owner: &str, name: &str) -> SrcRepoError {
+    SrcRepoError::Forge(format!("\"{owner}/{name}\" was not found"))
+}
+
+/// A REST API reached with an optional token, sent in `header` as
+/// `<prefix><token>`.
+struct Api {
+    url: String,
+    token: Option<String>,
+    header: &'static str,
+    prefix: &'static str,
+}
+
+impl Api {
+    fn new(
+        url: &str,
+        token: Option<String>,
+        header: &'static str,
+        prefix: &'static str,
+    ) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            token,
+            header,
+            prefix,
+        }
+    }
+
+    /// Get one page of `path`, or `None` if it doesn't exist.
+    fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Option<T>, SrcRepoError> {
+        let url = format!("{}/{path}", self.url);
+
+        log(&LogLevel::Debug, &format!("requesting {url} {query:?}"));
+
+        let mut request = ureq::get(&url)
+            .header("Accept", "application/json")
+            .header("User-Agent", "src");
+
+        if let Some(token) = &self.token {
+            request = request
+                .header(self.header, &format!("{}{token}", self.prefix));
+        }
+
+        for (key, value) in query {
+            request = request.query(*key, value);
+        }
+
+        match request.call() {
+            Ok(mut response) => response
+                .body_mut()
+                .read_json()
+                .map(Some)
+                .map_err(|error| SrcRepoError::Forge(error.to_string())),
+
+            Err(ureq::Error::StatusCode(404)) => Ok(None),
+            Err(error) => Err(SrcRepoError::Forge(error.to_string())),
+        }
+    }
+
+    /// Get every page of `path`, or `None` if it doesn't exist.
+    fn get_pages<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        page_size: (&str, usize),
+    ) -> Result<Option<Vec<T>>, SrcRepoError> {
+        let (page_size_key, page_size) = page_size;
+        let mut items: Vec<T> = vec![];
+
+        for page in 1.. {
+            let mut page_query = query.to_vec();
+
+            page_query.push(("page", page.to_string()));
+            page_query.push((page_size_key, page_size.to_string()));
+
+            let Some(page_items) = self.get::<Vec<T>>(path, &page_query)?
+            else {
+                return Ok(None);
+            };
+
+            let is_last_page = page_items.len() < page_size;
+
+            items.extend(page_items);
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        Ok(Some(items))
+    }
+
+    /// Get every page of the first of `paths` that exists.
+    fn get_first_pages<T: DeserializeOwned>(
+        &self,
+        owner: &str,
+        paths: &[(String, &[(&str, String)])],
+        page_size: (&str, usize),
+    ) -> Result<Vec<T>, SrcRepoError> {
+        for (path, query) in paths {
+            if let Some(items) = self.get_pages(path, query, page_size)? {
+                return Ok(items);
+            }
+        }
+
+        Err(SrcRepoError::Forge(format!("{owner:?} was not found")))
+    }
+}
+
+#[derive(Deserialize)]
+struct Login {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GithubRepo {
+    name: String,
+    owner: Login,
+    ssh_url: String,
+    archived: bool,
+    fork: bool,
+    private: bool,
+}
+
+impl From<GithubRepo> for RemoteRepo {
+    fn from(repo: GithubRepo) -> Self {
+        Self {
+            owner: repo.owner.login,
+            name: repo.name,
+            url: repo.ssh_url,
+            archived: repo.archived,
+            fork: repo.fork,
+            private: repo.private,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GithubFork {
+    parent: Option<GithubRepo>,
+}
+
+pub struct Github {
+    api: Api,
+}
+
+impl Github {
+    #[must_use]
+    pub fn new(api_url: &str, token: Option<String>) -> Self {
+        Self {
+            api: Api::new(api_url, token, "Authorization", "Bearer "),
+        }
+    }
+
+    /// The user the token belongs to, if there is a token.
+    fn authenticated_user(&self) -> Result<Option<String>, SrcRepoError> {
+        if self.api.token.is_none() {
+            return Ok(None);
+        }
+
+        Ok(self.api.get::<Login>("user", &[])?.map(|user| user.login))
+    }
+}
+
+impl Forge for Github {
+    fn list_repos(
+        &self,
+        owner: &str,
+    ) -> Result<Vec<RemoteRepo>, SrcRepoError> {
+        // Only the authenticated user's own endpoint includes their private
+        // repositories.
+        let user_path = if self
+            .authenticated_user()?
+            .is_some_and(|user| user.eq_ignore_ascii_case(owner))
+        {
+            (
+                "user/repos".to_string(),
+                [("affiliation", "owner".to_string())],
+            )
+        } else {
+            (
+                format!("users/{owner}/repos"),
+                [("type", "owner".to_string())],
+            )
+        };
+
+        let repos: Vec<GithubRepo> = self.api.get_first_pages(
+            owner,
+            &[
+                (
+                    format!("orgs/{owner}/repos"),
+                    &[("type", "all".to_string())],
+                ),
+                (user_path.0, &user_path.1),
+            ],
+            ("per_page", 100),
+        )?;
+
+        Ok(repos.into_iter().map(Into::into).collect())
+    }
+
+    fn get_parent(
+        &self,
+        owner: &str,
+        name: &str,
+    ) -> Result<Option<RemoteRepo>, SrcRepoError> {
+        let fork: GithubFork = self
+            .api
+            .get(&format!("repos/{owner}/{name}"), &[])?
+            .ok_or_else(|| not_found(owner, name))?;
+
+        Ok(fork.parent.map(Into::into))
+    }
+}
+
+#[derive(Deserialize)]
+struct GitlabNamespace {
+    full_path: String,
+}
+
+#[derive(Deserialize)]
+struct GitlabProject {
+    path: String,
+    namespace: GitlabNamespace,
+    ssh_url_to_repo: String,
+    archived: bool,
+    forked_from_project: Option<GitlabParent>,
+    visibility: String,
+}
+
+#[derive(Deserialize)]
+struct GitlabParent {
+    id: u64,
+}
+
+impl From<GitlabProject> for RemoteRepo {
+    fn from(project: GitlabProject) -> Self {
+        Self {
+            owner: project.namespace.full_path,
+            name: project.path,
+            url: project.ssh_url_to_repo,
+            archived: project.archived,
+            fork: project.forked_from_project.is_some(),
+            private: project.visibility != "public",
+        }
+    }
+}
+
+pub struct Gitlab {
+    api: Api,
+}
+
+impl Gitlab {
+    #[must_use]
+    pub fn new(api_url: &str, token: Option<String>) -> Self {
+        Self {
+            api: Api::new(api_url, token, "PRIVATE-TOKEN", ""),
+        }
+    }
+}
+
+impl Forge for Gitlab {
+    fn list_repos(
+        &self,
+        owner: &str,
+    ) -> Result<Vec<RemoteRepo>, SrcRepoError> {
+        let id = owner.replace('/', "%2F");
+
+        let projects: Vec<GitlabProject> = self.api.get_first_pages(
+            owner,
+            &[
+                (
+                    format!("groups/{id}/projects"),
+                    &[("include_subgroups", "true".to_string())],
+                ),
+                (format!("users/{id}/projects"), &[]),
+            ],
+            ("per_page", 100),
+        )?;
+
+        Ok(projects.into_iter().map(Into::into).collect())
+    }
+
+    fn get_parent(
+        &self,
+        owner: &str,
+        name: &str,
+    ) -> Result<Option<RemoteRepo>, SrcRepoError> {
+        let id = format!("{owner}/{name}").replace('/', "%2F");
+
+        let project: GitlabProject = self
+            .api
+            .get(&format!("projects/{id}"), &[])?
+            .ok_or_else(|| not_found(owner, name))?;
+
+        let Some(parent) = project.forked_from_project else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .api
+            .get::<GitlabProject>(&format!("projects/{}", parent.id), &[])?
+            .map(Into::into))
+    }
+}
+
+#[derive(Deserialize)]
+struct GiteaRepo {
+    name: String,
+    owner: Login,
+    ssh_url: String,
+    archived: bool,
+    fork: bool,
+    private: bool,
+}
+
+impl From<GiteaRepo> for RemoteRepo {
+    fn from(repo: GiteaRepo) -> Self {
+        Self {
+            owner: repo.owner.login,
+            name: repo.name,
+            url: repo.ssh_url,
+            archived: repo.archived,
+            fork: repo.fork,
+            private: repo.private,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GiteaFork {
+    parent: Option<GiteaRepo>,
+}
+
+pub struct Gitea {
+    api: Api,
+}
+
+impl Gitea {
+    #[must_use]
+    pub fn new(api_url: &str, token: Option<String>) -> Self {
+        Self {
+            api: Api::new(api_url, token, "Authorization", "token "),
+        }
+    }
+}
+
+impl Forge for Gitea {
+    fn list_repos(
+        &self,
+        owner: &str,
+    ) -> Result<Vec<RemoteRepo>, SrcRepoError> {
+        let repos: Vec<GiteaRepo> = self.api.get_first_pages(
+            owner,
+            &[
+                (format!("orgs/{owner}/repos"), &[]),
+                (format!("users/{owner}/repos"), &[]),
+            ],
+            ("limit", 50),
+        )?;
+
+        Ok(repos.into_iter().map(Into::into).collect())
+    }
+
+    fn get_parent(
+        &self,
+        owner: &str,
+        name: &str,
+    ) -> Result<Option<RemoteRepo>, SrcRepoError> {
+        let fork: GiteaFork = self
+            .api
+            .get(&format!("repos/{owner}/{name}"), &[])?
+            .ok_or_else(|| not_found(owner, name))?;
+
+        Ok(fork.parent.map(Into::into))
+    }
+}
+
+fn guess_kind(host: &str) -> Option<ForgeKind> {
+    if host.contains("github") {
+        Some(ForgeKind::Github)
+    } else if host.contains("gitlab") {
+        Some(ForgeKind::Gitlab)
+    } else if ["gitea", "forgejo", "codeberg"]
+        .iter()
+        .any(|name| host.contains(name))
+    {
+        Some(ForgeKind::Gitea)
+    } else {
+        None
+    }
+}
+
+fn default_api_url(kind: ForgeKind, host: &str) -> String {
+    match kind {
+        ForgeKind::Github if host == "github.com" => {
+            "https://api.github.com".to_string()
+        }
+
+        ForgeKind::Github => format!("https://{host}/api/v3"),
+        ForgeKind::Gitlab => format!("https://{host}/api/v4"),
+        ForgeKind::Gitea => format!("https://{host}/api/v1"),
+    }
+}
+
+fn env_token(kind: ForgeKind) -> Option<String> {
+    let names: &[&str] = match kind {
+        ForgeKind::Github => &["GITHUB_TOKEN", "GH_TOKEN"],
+        ForgeKind::Gitlab => &["GITLAB_TOKEN"],
+        ForgeKind::Gitea => &["GITEA_TOKEN", "FORGEJO_TOKEN"],
+    };
+
+    names.iter().find_map(|name| env::var(name).ok())
+}
+
+/// The forge at `host`, set up from `[forges."<host>"]` in the config.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if the kind of forge isn't configured and can't
+/// be guessed from `host`
+pub fn get_forge(
+    config: &Config,
+    host: &str,
+) -> Result<Box<dyn Forge>, SrcRepoError> {
+    let forge_config = config.forges.get(host).cloned().unwrap_or_default();
+
+    let kind = forge_config
+        .kind
+        .or_else(|| guess_kind(host))
+        .ok_or_else(|| SrcRepoError::UnknownForge(host.to_string()))?;
+
+    let api_url = forge_config
+        .api_url
+        .unwrap_or_else(|| default_api_url(kind, host));
+    let token = forge_config.token.or_else(|| env_token(kind));
+
+    Ok(match kind {
+        ForgeKind::Github => Box::new(Github::new(&api_url, token)),
+        ForgeKind::Gitlab => Box::new(Gitlab::new(&api_url, token)),
+        ForgeKind::Gitea => Box::new(Gitea::new(&api_url, token)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use tiny_http::{Header, Response, Server};
+
+    use super::*;
+
+    /// The url and token header of each request.
+    type Requests = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+    /// Serve the canned `responses`, keyed by request url, on a local port,
+    /// recording each request's url and token header. Anything else is a 404.
+    fn serve(responses: &[(&str, &str)]) -> (String, Requests) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+        let responses: HashMap<String, String> = responses
+            .iter()
+            .map(|(url, body)| ((*url).to_string(), (*body).to_string()))
+            .collect();
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded_requests = Arc::clone(&requests);
+
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let token = request
+                    .headers()
+                    .iter()
+                    .find(|header| {
+                        header.field.equiv("Authorization")
+                            || header.field.equiv("PRIVATE-TOKEN")
+                    })
+                    .map(|header| header.value.to_string());
+
+                recorded_requests
+                    .lock()
+                    .unwrap()
+                    .push((request.url().to_string(), token));
+
+                let (status, body) = responses
+                    .get(request.url())
+                    .map_or((404, "{}"), |body| (200, body.as_str()));
+
+                let _ = request.respond(
+                    Response::from_string(body)
+                        .with_status_code(status)
+                        .with_header(
+                            Header::from_bytes(
+                                "Content-Type",
+                                "application/json",
+                            )
+                            .unwrap(),
+                        ),
+                );
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn it_lists_a_github_users_repos_after_checking_for_an_org() {
+        let (url, requests) = serve(&[(
+            "/users/me/repos?type=owner&page=1&per_page=100",
+            r#"[
+                {"name": "a", "owner": {"login": "me"},
+                 "ssh_url": "git@github.com:me/a.git",
+                 "archived": false, "fork": false, "private": false},
+                {"name": "b", "owner": {"login": "me"},
+                 "ssh_url": "git@github.com:me/b.git",
+                 "archived": true, "fork": true, "private": true}
+            ]"#,
+        )]);
+
+        let repos = Github::new(&url, None).list_repos("me").unwrap();
+
+        assert_eq!(
+            repos,
+            vec![
+                RemoteRepo {
+                    owner: "me".to_string(),
+                    name: "a".to_string(),
+                    url: "git@github.com:me/a.git".to_string(),
+                    archived: false,
+                    fork: false,
+                    private: false,
+                },
+                RemoteRepo {
+                    owner: "me".to_string(),
+                    name: "b".to_string(),
+                    url: "git@github.com:me/b.git".to_string(),
+                    archived: true,
+                    fork: true,
+                    private: true,
+                },
+            ]
+        );
+
+        assert_eq!(
+            requests.lock().unwrap()[0].0,
+            "/orgs/me/repos?type=all&page=1&per_page=100"
+        );
+    }
+
+    #[test]
+    fn it_lists_the_authenticated_github_users_private_repos() {
+        let (url, requests) = serve(&[
+            ("/user", r#"{"login": "Me"}"#),
+            (
+                "/user/repos?affiliation=owner&page=1&per_page=100",
+                r#"[{"name": "secret", "owner": {"login": "me"},
+                     "ssh_url": "git@github.com:me/secret.git",
+                     "archived": false, "fork": false, "private": true}]"#,
+            ),
+        ]);
+
+        let repos = Github::new(&url, Some("abc".to_string()))
+            .list_repos("me")
+            .unwrap();
+
+        assert_eq!(repos.len(), 1);
+        assert!(repos[0].private);
+
+        assert!(
+            requests
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|(_, token)| token.as_deref() == Some("Bearer abc"))
+        );
+    }
+
+    #[test]
+    fn it_pages_through_gitea_repos() {
+        let repo = r#"{"name": "tool", "owner": {"login": "org"},
+            "ssh_url": "git@codeberg.org:org/tool.git",
+            "archived": false, "fork": false, "private": false}"#;
+        let full_page = format!("[{}]", vec![repo; 50].join(","));
+        let last_page = format!("[{repo}]");
+
+        let (url, _) = serve(&[
+            ("/orgs/org/repos?page=1&limit=50", &full_page),
+            ("/orgs/org/repos?page=2&limit=50", &last_page),
+        ]);
+
+        assert_eq!(
+            Gitea::new(&url, None).list_repos("org").unwrap().len(),
+            51
+        );
+    }
+
+    #[test]
+    fn it_lists_gitlab_subgroup_projects() {
+        let (url, requests) = serve(&[(
+            "/groups/team%2Fsub/projects?include_subgroups=true&page=1&per_page=100",
+            r#"[{"path": "app", "namespace": {"full_path": "team/sub"},
+                 "ssh_url_to_repo": "git@gitlab.com:team/sub/app.git",
+                 "archived": false, "forked_from_project": {"id": 1},
+                 "visibility": "internal"}]"#,
+        )]);
+
+        let repos = Gitlab::new(&url, Some("abc".to_string()))
+            .list_repos("team/sub")
+            .unwrap();
+
+        assert_eq!(repos[0].owner, "team/sub");
+        assert!(repos[0].fork);
+        assert!(repos[0].private);
+        assert_eq!(requests.lock().unwrap()[0].1.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn it_fails_when_the_owner_does_not_exist() {
+        let (url, _) = serve(&[]);
+
+        assert!(matches!(
+            Gitea::new(&url, None).list_repos("nobody"),
+            Err(SrcRepoError::Forge(_))
+        ));
+    }
+
+    #[test]
+    fn it_finds_the_parent_of_a_fork() {
+        let (url, _) = serve(&[
+            (
+                "/repos/me/fork",
+                r#"{"parent": {"name": "tool", "owner": {"login": "org"},
+                    "ssh_url": "git@github.com:org/tool.git",
+                    "archived": false, "fork": false, "private": false}}"#,
+            ),
+            ("/repos/me/tool", r#"{"parent": null}"#),
+        ]);
+
+        let github = Github::new(&url, None);
+
+        assert_eq!(
+            github.get_parent("me", "fork").unwrap().unwrap().url,
+            "git@github.com:org/tool.git"
+        );
+
+        assert_eq!(github.get_parent("me", "tool").unwrap(), None);
+        assert!(github.get_parent("me", "missing").is_err());
+    }
+
+    #[test]
+    fn it_finds_the_parent_of_a_gitlab_fork_by_id() {
+        let (url, _) = serve(&[
+            (
+                "/projects/me%2Ffork",
+                r#"{"path": "fork", "namespace": {"full_path": "me"},
+                    "ssh_url_to_repo": "git@gitlab.com:me/fork.git",
+                    "archived": false, "forked_from_project": {"id": 7},
+                    "visibility": "public"}"#,
+            ),
+            (
+                "/projects/7",
+                r#"{"path": "app", "namespace": {"full_path": "team/sub"},
+                    "ssh_url_to_repo": "git@gitlab.com:team/sub/app.git",
+                    "archived": false, "visibility": "public"}"#,
+            ),
+        ]);
+
+        let parent = Gitlab::new(&url, None)
+            .get_parent("me", "fork")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(parent.owner, "team/sub");
+        assert_eq!(parent.url, "git@gitlab.com:team/sub/app.git");
+    }
+
+    #[test]
+    fn it_filters_archived_repos_forks_and_visibility() {
+        let repo = RemoteRepo {
+            owner: "me".to_string(),
+            name: "a".to_string(),
+            url: "git@github.com:me/a.git".to_string(),
+            archived: false,
+            fork: true,
+            private: true,
+        };
+
+        assert!(!RemoteRepoFilter::default().matches(&repo));
+
+        assert!(
+            RemoteRepoFilter {
+                forks: true,
+                ..RemoteRepoFilter::default()
+            }
+            .matches(&repo)
+        );
+
+        assert!(
+            !RemoteRepoFilter {
+                forks: true,
+                visibility: Visibility::Public,
+                ..RemoteRepoFilter::default()
+            }
+            .matches(&repo)
+        );
+    }
+
+    #[test]
+    fn it_reads_tokens_but_never_writes_them() {
+        let forge: ForgeConfig =
+            toml::from_str("kind = \"gitea\"\ntoken = \"secret-token\"\n")
+                .unwrap();
+
+        assert_eq!(forge.token.as_deref(), Some("secret-token"));
+
+        let forge = toml::to_string(&forge).unwrap();
+
+        assert!(forge.contains("kind = \"gitea\""));
+        assert!(!forge.contains("token"));
+    }
+}
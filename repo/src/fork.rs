@@ -0,0 +1,99 @@
+use std::path::Path;
+
+use git2::{BranchType, Repository};
+
+use crate::error::SrcRepoError;
+use crate::repo::find_remote_name;
+
+/// The remote that forks track the repository they were forked from with.
+pub const UPSTREAM_REMOTE: &str = "upstream";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ForkStatus {
+    /// The default branch of the upstream repository
+    pub branch: String,
+
+    /// Commits on the upstream default branch and not on the local one, if
+    /// there is a local one
+    pub behind: Option<usize>,
+}
+
+#[must_use]
+pub fn has_upstream_remote(repo_path: &Path) -> bool {
+    Repository::open(repo_path).is_ok_and(|repository| {
+        repository.find_remote(UPSTREAM_REMOTE).is_ok()
+    })
+}
+
+fn get_remote_head(repository: &Repository, remote: &str) -> Option<String> {
+    let prefix = format!("refs/remotes/{remote}/");
+
+    repository
+        .find_reference(&format!("{prefix}HEAD"))
+        .ok()?
+        .symbolic_target()?
+        .strip_prefix(&prefix)
+        .map(ToString::to_string)
+}
+
+/// The default branch of the `upstream` remote: the branch its `HEAD` points
+/// at, or failing that, the one the `HEAD` of the remote chosen from
+/// `remote_names` points at.
+fn get_upstream_branch(
+    repository: &Repository,
+    remote_names: &[String],
+) -> Option<String> {
+    get_remote_head(repository, UPSTREAM_REMOTE).or_else(|| {
+        let remote = find_remote_name(repository, remote_names).ok()??;
+
+        get_remote_head(repository, &remote).filter(|branch| {
+            repository
+                .find_branch(
+                    &format!("{UPSTREAM_REMOTE}/{branch}"),
+                    BranchType::Remote,
+                )
+                .is_ok()
+        })
+    })
+}
+
+/// How the repository at `repo_path` compares to the repository it was forked
+/// from, or `None` if it has no `upstream` remote.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if `repo_path` is not a git repository
+pub fn get_fork_status(
+    repo_path: &Path,
+    remote_names: &[String],
+) -> Result<Option<ForkStatus>, SrcRepoError> {
+    let repository = Repository::open(repo_path)?;
+
+    if repository.find_remote(UPSTREAM_REMOTE).is_err() {
+        return Ok(None);
+    }
+
+    let Some(branch) = get_upstream_branch(&repository, remote_names) else {
+        return Ok(None);
+    };
+
+    let behind = repository
+        .find_branch(&branch, BranchType::Local)
+        .ok()
+        .and_then(|local| local.get().target())
+        .zip(
+            repository
+                .find_branch(
+                    &format!("{UPSTREAM_REMOTE}/{branch}"),
+                    BranchType::Remote,
+                )
+                .ok()
+                .and_then(|upstream| upstream.get().target()),
+        )
+        .and_then(|(local, upstream)| {
+            repository.graph_ahead_behind(local, upstream).ok()
+        })
+        .map(|(_, behind)| behind);
+
+    Ok(Some(ForkStatus { branch, behind }))
+}
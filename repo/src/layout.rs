@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use crate::error::SrcRepoError;
+use crate::repo::Repo;
+
+pub const DEFAULT_LAYOUT: &str = "{host}/{owner}/{name}";
+
+const PLACEHOLDERS: [&str; 4] = ["{host?}", "{host}", "{owner}", "{name}"];
+
+/// Where repositories are kept under a root.
+///
+/// The template is made of `/`-separated segments using `{host}`, `{owner}`
+/// and `{name}`. `{host?}` is the host, left out for the default host.
+/// Segments that come out empty are dropped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Layout {
+    template: String,
+    default_host: Option<String>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_LAYOUT.to_string(),
+            default_host: None,
+        }
+    }
+}
+
+impl Layout {
+    /// # Errors
+    ///
+    /// Will return `SrcRepoError::Layout` if `template` uses an unknown
+    /// placeholder, steps out of the root, or doesn't end with `{name}`
+    pub fn new(
+        template: &str,
+        default_host: Option<&str>,
+    ) -> Result<Self, SrcRepoError> {
+        let segments: Vec<&str> = template.split('/').collect();
+
+        let unknown_placeholder = segments.iter().any(|segment| {
+            PLACEHOLDERS
+                .iter()
+                .fold((*segment).to_string(), |segment, placeholder| {
+                    segment.replace(placeholder, "")
+                })
+                .contains(['{', '}'])
+        });
+
+        if unknown_placeholder
+            || template.starts_with('/')
+            || segments.contains(&"..")
+            || !segments
+                .last()
+                .is_some_and(|segment| segment.contains("{name}"))
+        {
+            return Err(SrcRepoError::Layout(template.to_string()));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            default_host: default_host.map(ToString::to_string),
+        })
+    }
+
+    /// The path of `repo` relative to the root.
+    #[must_use]
+    pub fn path(&self, repo: &Repo) -> PathBuf {
+        let optional_host =
+            if self.default_host.as_ref().is_some_and(|default_host| {
+                default_host.eq_ignore_ascii_case(&repo.host)
+            }) {
+                ""
+            } else {
+                &repo.host
+            };
+
+        self.template
+            .split('/')
+            .map(|segment| {
+                segment
+                    .replace("{host?}", optional_host)
+                    .replace("{host}", &repo.host)
+                    .replace("{owner}", &repo.owner)
+                    .replace("{name}", &repo.name)
+            })
+            .filter(|segment| !segment.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::LOCAL_HOST;
+
+    #[test]
+    fn it_places_repos_by_template() {
+        let repo = Repo::new("github.com", "me", "src", None, "");
+        let other = Repo::new("gitlab.com", "group/subgroup", "src", None, "");
+        let local = Repo::new(LOCAL_HOST, "", "notes", None, "");
+
+        let layout =
+            Layout::new("{host?}/{owner}/{name}", Some("github.com")).unwrap();
+
+        assert_eq!(layout.path(&repo), PathBuf::from("me/src"));
+
+        assert_eq!(
+            layout.path(&other),
+            PathBuf::from("gitlab.com/group/subgroup/src")
+        );
+
+        assert_eq!(layout.path(&local), PathBuf::from("local/notes"));
+
+        assert_eq!(
+            Layout::new("{name}", None).unwrap().path(&other),
+            PathBuf::from("src")
+        );
+
+        for template in ["{host}/{owner}", "{name}/{branch}", "../{name}"] {
+            assert!(Layout::new(template, None).is_err());
+        }
+    }
+}
@@ -1,4 +1,23 @@
+pub mod alias;
+pub mod archive;
+pub mod backup;
+pub mod clone;
 pub mod config;
+pub mod doctor;
 pub mod error;
+pub mod foreign;
+pub mod fork;
+pub mod forge;
+pub mod layout;
 pub mod list;
+pub mod log;
+pub mod manifest;
+pub mod metadata;
+pub mod mirror;
+pub mod rename;
 pub mod repo;
+pub mod root;
+pub mod status;
+pub mod submodule;
+pub mod tag;
+pub mod worktree;
@@ -1,55 +1,102 @@
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use dirs::home_dir;
 use walkdir::{DirEntry, WalkDir};
 
-use crate::config::{Config, get_root_directory};
+use crate::alias::find_alias;
+use crate::config::Config;
 use crate::error::SrcRepoError;
+use crate::log::{LogLevel, log};
+use crate::metadata::read_metadata;
 use crate::repo::Repo;
-
+use crate::root::{Root, root_for};
+use crate::submodule::{get_submodules, is_submodule_path};
+use crate::tag::has_tag;
+use crate::worktree::{get_worktrees, is_worktrees_path};
+
+/// Find the repositories managed in `root_directory`: the git repositories
+/// under it that aren't inside another repository, wherever the layout put
+/// them.
 #[must_use]
 pub fn get_managed_repo_paths(root_directory: &str) -> Vec<String> {
-    WalkDir::new(root_directory)
-        .into_iter()
-        .filter_map(|path| {
-            path.as_ref().map_or(None, |path| {
-                if !path.file_type().is_dir() || path.depth() != 3 {
-                    return None;
-                }
+    let mut paths = vec![];
+    let mut entries = WalkDir::new(root_directory).min_depth(1).into_iter();
 
-                path.path().strip_prefix(root_directory).map_or(
-                    None,
-                    |dir_entry| {
-                        Some(
-                            PathBuf::from(root_directory)
-                                .join(dir_entry)
-                                .to_string_lossy()
-                                .to_string(),
-                        )
-                    },
-                )
-            })
-        })
+    while let Some(entry) = entries.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+
+        if is_worktrees_path(entry.path()) {
+            entries.skip_current_dir();
+
+            continue;
+        }
+
+        if !entry.path().join(".git").exists() {
+            continue;
+        }
+
+        entries.skip_current_dir();
+
+        if let Ok(relative_path) = entry.path().strip_prefix(root_directory) {
+            paths.push(
+                PathBuf::from(root_directory)
+                    .join(relative_path)
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+    }
+
+    paths
+}
+
+#[must_use]
+pub fn get_all_managed_repo_paths(roots: &[Root]) -> Vec<String> {
+    roots
+        .iter()
+        .flat_map(|root| get_managed_repo_paths(&root.path))
         .collect()
 }
 
+/// The repositories in `roots`. Those whose remotes can't identify them are
+/// reported and left out.
+///
 /// # Errors
 ///
-/// Will return `SrcRepoError` if it fails to parse a repo in `root_directory`
+/// Will return `SrcRepoError` if `all` is set and it fails to find the
+/// unmanaged repositories
 pub fn get_repos(
-    root_directory: &str,
+    config: &Config,
+    roots: &[Root],
     all: bool,
     hidden: bool,
 ) -> Result<Vec<Repo>, SrcRepoError> {
-    let paths = if all {
-        get_repo_paths(None, hidden)?
-    } else {
-        get_managed_repo_paths(root_directory)
-    };
+    let mut paths = get_all_managed_repo_paths(roots);
 
-    paths.iter().map(|repo| Repo::from(repo)).collect()
+    if all {
+        paths.extend(get_repo_paths(roots, hidden)?);
+    }
+
+    Ok(paths
+        .iter()
+        .filter_map(|path| match Repo::from(path, &config.remote_names) {
+            Ok(repo) => Some(repo),
+
+            Err(error) => {
+                log(&LogLevel::Warning, &format!("skipping {path}: {error}"));
+
+                None
+            }
+        })
+        .collect())
 }
 
 fn is_git_repo(path: &DirEntry) -> bool {
@@ -111,22 +158,28 @@ fn unique_repos(repos: &[Repo]) -> Vec<Repo> {
     unique_repos
 }
 
-fn list_repos(
+/// The repositories at `repo_paths` matching `host`, `owner`, `name` (or
+/// `me`) and `tag`. Those that can't be identified are reported and left out.
+fn filter_repos(
     config: &Config,
     repo_paths: &[String],
     host: Option<&String>,
     owner: Option<&String>,
     name: Option<&String>,
+    tag: Option<&String>,
     me: bool,
-    no_host: bool,
-    no_owner: bool,
-    path: bool,
-    unique: bool,
-    sort_by: Option<&SortBy>,
-) -> Vec<String> {
+) -> Result<Vec<Repo>, SrcRepoError> {
     let mut repos: Vec<Repo> = repo_paths
         .iter()
-        .filter_map(|path| Repo::from(path).ok())
+        .filter_map(|path| match Repo::from(path, &config.remote_names) {
+            Ok(repo) => Some(repo),
+
+            Err(error) => {
+                log(&LogLevel::Warning, &format!("skipping {path}: {error}"));
+
+                None
+            }
+        })
         .collect();
 
     if let Some(host) = host {
@@ -156,6 +209,36 @@ fn list_repos(
         repos.retain(|repo| &repo.name == name);
     }
 
+    if let Some(tag) = tag {
+        let metadata = read_metadata()?;
+
+        repos.retain(|repo| has_tag(config, &metadata, repo, tag));
+    }
+
+    Ok(repos)
+}
+
+fn list_repos(
+    config: &Config,
+    roots: &[Root],
+    repo_paths: &[String],
+    host: Option<&String>,
+    owner: Option<&String>,
+    name: Option<&String>,
+    tag: Option<&String>,
+    me: bool,
+    no_host: bool,
+    no_owner: bool,
+    aliases: bool,
+    path: bool,
+    worktrees: bool,
+    submodules: bool,
+    unique: bool,
+    sort_by: Option<&SortBy>,
+) -> Result<Vec<String>, SrcRepoError> {
+    let mut repos =
+        filter_repos(config, repo_paths, host, owner, name, tag, me)?;
+
     if let Some(sort_by) = &sort_by {
         match sort_by {
             SortBy::Host => repos.sort_by(|a, b| a.host.cmp(&b.host)),
@@ -168,24 +251,87 @@ fn list_repos(
         repos = unique_repos(&repos);
     }
 
-    let mut formatted_repos: Vec<String> =
-        repos
-            .iter()
-            .filter_map(|repo| {
-                if path {
-                    repo.local_source_path.as_ref().map_or_else(
-                        || {
-                            Some(repo.managed_path_name(
-                                &get_root_directory().ok()?,
-                            ))
+    // With `aliases`, show each host as the first alias standing for it.
+    let display = |repo: &Repo| {
+        let alias = find_alias(&config.aliases, &repo.host)
+            .filter(|_| aliases && !repo.is_local());
+
+        alias.map_or_else(
+            || repo.display(no_host, no_owner),
+            |alias| {
+                let mut repo = repo.clone();
+
+                repo.host.clone_from(alias);
+                repo.display(no_host, no_owner)
+            },
+        )
+    };
+
+    let mut formatted_repos: Vec<String> = repos
+        .iter()
+        .flat_map(|repo| {
+            let repo_path = repo
+                .local_source_path
+                .clone()
+                .or_else(|| Some(repo.managed_path(root_for(roots, repo)?)));
+
+            let mut formatted = vec![];
+
+            if path {
+                formatted.extend(
+                    repo_path
+                        .iter()
+                        .map(|path| path.to_string_lossy().to_string()),
+                );
+            } else {
+                formatted.push(display(repo));
+            }
+
+            if worktrees && let Some(repo_path) = &repo_path {
+                formatted.extend(
+                    get_worktrees(repo_path).unwrap_or_default().iter().map(
+                        |worktree| {
+                            if path {
+                                worktree.path.to_string_lossy().to_string()
+                            } else {
+                                format!(
+                                    "{} [{}]",
+                                    display(repo),
+                                    worktree
+                                        .branch
+                                        .as_deref()
+                                        .unwrap_or("detached")
+                                )
+                            }
                         },
-                        |path| Some(path.to_string_lossy().to_string()),
-                    )
-                } else {
-                    Some(repo.display(no_host, no_owner))
-                }
-            })
-            .collect();
+                    ),
+                );
+            }
+
+            if submodules && let Some(repo_path) = &repo_path {
+                formatted.extend(
+                    get_submodules(repo_path).unwrap_or_default().iter().map(
+                        |submodule| {
+                            if path {
+                                repo_path
+                                    .join(&submodule.path)
+                                    .to_string_lossy()
+                                    .to_string()
+                            } else {
+                                format!(
+                                    "{} ({})",
+                                    display(repo),
+                                    submodule.path.display()
+                                )
+                            }
+                        },
+                    ),
+                );
+            }
+
+            formatted
+        })
+        .collect();
 
     if unique {
         formatted_repos = formatted_repos
@@ -199,42 +345,111 @@ fn list_repos(
         formatted_repos.sort_by(|a, b| sort_case_insensitive(a, b));
     }
 
-    formatted_repos
+    Ok(formatted_repos)
 }
 
 /// # Errors
 ///
-/// Will return `SrcRepoError` if it fails to merge configuration from the file
-/// and the environment
+/// Will return `SrcRepoError` if it fails to read the metadata file when
+/// filtering by `tag`
 pub fn list_managed_repos(
     config: &Config,
+    roots: &[Root],
     host: Option<&String>,
     owner: Option<&String>,
     name: Option<&String>,
+    tag: Option<&String>,
     me: bool,
     no_host: bool,
     no_owner: bool,
+    aliases: bool,
     path: bool,
+    worktrees: bool,
+    submodules: bool,
     sort_by: Option<&SortBy>,
 ) -> Result<Vec<String>, SrcRepoError> {
-    Ok(list_repos(
+    list_repos(
         config,
-        &get_managed_repo_paths(&get_root_directory()?),
+        roots,
+        &get_all_managed_repo_paths(roots),
         host,
         owner,
         name,
+        tag,
         me,
         no_host,
         no_owner,
+        aliases,
         path,
+        worktrees,
+        submodules,
         false,
         sort_by,
-    ))
+    )
+}
+
+/// The managed repositories matching the same filters as
+/// `list_managed_repos`, with their paths, sorted by path.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to read the metadata file when
+/// filtering by `tag`
+pub fn get_managed_repos(
+    config: &Config,
+    roots: &[Root],
+    host: Option<&String>,
+    owner: Option<&String>,
+    name: Option<&String>,
+    tag: Option<&String>,
+    me: bool,
+) -> Result<Vec<(Repo, PathBuf)>, SrcRepoError> {
+    let mut repos: Vec<(Repo, PathBuf)> = filter_repos(
+        config,
+        &get_all_managed_repo_paths(roots),
+        host,
+        owner,
+        name,
+        tag,
+        me,
+    )?
+    .into_iter()
+    .filter_map(|repo| {
+        let path = repo.local_source_path.clone()?;
+
+        Some((repo, path))
+    })
+    .collect();
+
+    repos.sort_by(|(_, a), (_, b)| {
+        sort_case_insensitive(&a.to_string_lossy(), &b.to_string_lossy())
+    });
+
+    Ok(repos)
 }
 
-fn is_managed_path(path: &DirEntry, root_directory: Option<&str>) -> bool {
-    root_directory.is_some_and(|root_directory| {
-        path.depth() == 4 && path.path().starts_with(root_directory)
+/// Whether `path` is a managed repository, or one of the worktrees kept next
+/// to it. Repositories nested inside a managed repository are not managed.
+fn is_managed_path(path: &DirEntry, roots: &[Root]) -> bool {
+    roots.iter().any(|root| {
+        path.path()
+            .strip_prefix(&root.path)
+            .is_ok_and(|relative_path| {
+                let components: Vec<&Path> =
+                    relative_path.iter().map(Path::new).collect();
+
+                if components.iter().any(|name| is_worktrees_path(name)) {
+                    return true;
+                }
+
+                !components.is_empty()
+                    && path
+                        .path()
+                        .ancestors()
+                        .skip(1)
+                        .take(components.len() - 1)
+                        .all(|ancestor| !ancestor.join(".git").exists())
+            })
     })
 }
 
@@ -242,11 +457,14 @@ fn convert_to_string(path: &DirEntry) -> String {
     path.path().to_string_lossy().to_string()
 }
 
+/// Find git repositories in `$HOME` that are not managed in any of `roots`,
+/// leaving out submodule checkouts.
+///
 /// # Errors
 ///
 /// Will return `SrcRepoError` if it fails to determine the `$HOME` directory
 pub fn get_repo_paths(
-    root_directory: Option<&str>,
+    roots: &[Root],
     hidden: bool,
 ) -> Result<Vec<String>, SrcRepoError> {
     let home_dir = home_dir().ok_or(SrcRepoError::HomeDir)?;
@@ -257,7 +475,8 @@ pub fn get_repo_paths(
             path.as_ref().map_or(None, |path| {
                 if !path.file_type().is_dir()
                     || !path.path().join(".git").exists()
-                    || is_managed_path(path, root_directory)
+                    || is_managed_path(path, roots)
+                    || is_submodule_path(path.path())
                     || (!hidden
                         && path
                             .path()
@@ -271,22 +490,7 @@ pub fn get_repo_paths(
                     return None;
                 }
 
-                root_directory.map_or_else(
-                    || Some(convert_to_string(path)),
-                    |root_directory| {
-                        path.path().strip_prefix(root_directory).map_or_else(
-                            |_| Some(convert_to_string(path)),
-                            |dir_entry| {
-                                Some(
-                                    PathBuf::from(root_directory)
-                                        .join(dir_entry)
-                                        .to_string_lossy()
-                                        .to_string(),
-                                )
-                            },
-                        )
-                    },
-                )
+                Some(convert_to_string(path))
             })
         })
         .collect())
@@ -294,60 +498,80 @@ pub fn get_repo_paths(
 
 /// # Errors
 ///
-/// Will return `SrcRepoError` if it fails to determine the `$HOME` directory
+/// Will return `SrcRepoError` if it fails to determine the `$HOME` directory,
+/// or to read the metadata file when filtering by `tag`
 pub fn list_unmanaged_repos(
     config: &Config,
+    roots: &[Root],
     hidden: bool,
     host: Option<&String>,
     owner: Option<&String>,
     name: Option<&String>,
+    tag: Option<&String>,
     me: bool,
     no_host: bool,
     no_owner: bool,
     path: bool,
     sort_by: Option<&SortBy>,
 ) -> Result<Vec<String>, SrcRepoError> {
-    Ok(list_repos(
+    list_repos(
         config,
-        &get_repo_paths(Some(&get_root_directory()?), hidden)?,
+        roots,
+        &get_repo_paths(roots, hidden)?,
         host,
         owner,
         name,
+        tag,
         me,
         no_host,
         no_owner,
+        false,
         path,
+        false,
+        false,
         true,
         sort_by,
-    ))
+    )
 }
 
 /// # Errors
 ///
-/// Will return `SrcRepoError` if it fails to determine the `$HOME` directory
+/// Will return `SrcRepoError` if it fails to determine the `$HOME` directory,
+/// or to read the metadata file when filtering by `tag`
 pub fn list_all_repos(
     config: &Config,
+    roots: &[Root],
     hidden: bool,
     host: Option<&String>,
     owner: Option<&String>,
     name: Option<&String>,
+    tag: Option<&String>,
     me: bool,
     no_host: bool,
     no_owner: bool,
     path: bool,
     sort_by: Option<&SortBy>,
 ) -> Result<Vec<String>, SrcRepoError> {
-    Ok(list_repos(
+    let mut repo_paths = get_all_managed_repo_paths(roots);
+
+    repo_paths.extend(get_repo_paths(roots, hidden)?);
+
+    list_repos(
         config,
-        &get_repo_paths(None, hidden)?,
+        roots,
+        &repo_paths,
         host,
         owner,
         name,
+        tag,
         me,
         no_host,
         no_owner,
+        false,
         path,
+        false,
+        false,
         true,
         sort_by,
-    ))
+    )
 }
@@ -0,0 +1,28 @@
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Error,
+    Warning,
+    Info,
+    Debug,
+    Trace,
+}
+
+pub type Logger = fn(LogLevel, &str);
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Install the function that receives every message logged by `src`.
+///
+/// Only the first logger is kept. Until one is installed, messages are
+/// discarded, so that the library never prints on its own.
+pub fn set_logger(logger: Logger) {
+    let _ = LOGGER.set(logger);
+}
+
+pub fn log(level: &LogLevel, message: &str) {
+    if let Some(logger) = LOGGER.get() {
+        logger(*level, message);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::SrcRepoError;
+use crate::list::get_all_managed_repo_paths;
+use crate::log::{LogLevel, log};
+use crate::metadata::Metadata;
+use crate::repo::Repo;
+use crate::root::Root;
+use crate::tag::get_tags;
+
+/// A record of one managed repository, with enough information to clone it
+/// again on another machine.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ManifestRepo {
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remotes: BTreeMap<String, String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+impl ManifestRepo {
+    /// The repository to clone: the `origin` remote if there is one,
+    /// otherwise the remote implied by `host`, `owner` and `name`.
+    #[must_use]
+    pub fn repo(&self) -> Repo {
+        let url = self.remotes.get("origin").cloned().unwrap_or_else(|| {
+            format!("{}:{}/{}", self.host, self.owner, self.name)
+        });
+
+        Repo::new(&self.host, &self.owner, &self.name, None, &url)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub repos: Vec<ManifestRepo>,
+}
+
+pub(crate) fn get_current_branch(repository: &Repository) -> Option<String> {
+    let head = repository.head().ok()?;
+
+    if head.is_branch() {
+        head.shorthand().map(ToString::to_string)
+    } else {
+        None
+    }
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if `path` is not a git repository
+pub fn get_manifest_repo(
+    config: &Config,
+    metadata: &Metadata,
+    repo: &Repo,
+    path: &Path,
+) -> Result<ManifestRepo, SrcRepoError> {
+    let repository = Repository::open(path)?;
+    let mut remotes = BTreeMap::new();
+
+    for name in repository.remotes()?.iter().flatten() {
+        if let Some(url) = repository.find_remote(name)?.url() {
+            remotes.insert(name.to_string(), url.to_string());
+        }
+    }
+
+    Ok(ManifestRepo {
+        host: repo.host.clone(),
+        owner: repo.owner.clone(),
+        name: repo.name.clone(),
+        remotes,
+        branch: get_current_branch(&repository),
+        tags: get_tags(config, metadata, repo),
+    })
+}
+
+/// Describe every repository managed in `roots`. Repositories that can't be
+/// read are reported and left out.
+#[must_use]
+pub fn create_manifest(
+    config: &Config,
+    metadata: &Metadata,
+    roots: &[Root],
+) -> Manifest {
+    let mut repos: Vec<ManifestRepo> = get_all_managed_repo_paths(roots)
+        .iter()
+        .filter_map(|path| {
+            match Repo::from(path, &config.remote_names).and_then(|repo| {
+                get_manifest_repo(config, metadata, &repo, Path::new(path))
+            }) {
+                Ok(repo) => Some(repo),
+
+                Err(error) => {
+                    log(
+                        &LogLevel::Warning,
+                        &format!(
+                            "leaving {path} out of the manifest: {error}"
+                        ),
+                    );
+
+                    None
+                }
+            }
+        })
+        .collect();
+
+    repos.sort_by(|a, b| {
+        (&a.host, &a.owner, &a.name).cmp(&(&b.host, &b.owner, &b.name))
+    });
+
+    Manifest { repos }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_back_what_it_writes() {
+        let manifest = Manifest {
+            repos: vec![ManifestRepo {
+                host: "github.com".to_string(),
+                owner: "me".to_string(),
+                name: "tool".to_string(),
+                remotes: BTreeMap::from([
+                    (
+                        "fork".to_string(),
+                        "git@github.com:other/tool.git".to_string(),
+                    ),
+                    (
+                        "origin".to_string(),
+                        "git@github.com:me/tool.git".to_string(),
+                    ),
+                ]),
+                branch: Some("feature".to_string()),
+                tags: BTreeSet::from([
+                    "tools".to_string(),
+                    "work".to_string(),
+                ]),
+            }],
+        };
+
+        let manifest: Manifest =
+            toml::from_str(&toml::to_string(&manifest).unwrap()).unwrap();
+        let manifest_repo = &manifest.repos[0];
+
+        assert_eq!(manifest.repos.len(), 1);
+        assert_eq!(manifest_repo.remotes.len(), 2);
+        assert_eq!(manifest_repo.branch.as_deref(), Some("feature"));
+        assert!(manifest_repo.tags.contains("work"));
+        assert_eq!(manifest_repo.repo().url(), "git@github.com:me/tool.git");
+    }
+
+    #[test]
+    fn it_falls_back_to_the_host_owner_and_name_without_an_origin() {
+        let manifest: Manifest = toml::from_str(
+            "[[repos]]\nhost = \"github.com\"\nowner = \"me\"\nname = \"tool\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.repos[0].repo().url(), "git@github.com:me/tool");
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use dirs::data_dir;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SrcRepoError;
+use crate::repo::{LOCAL_HOST, Repo};
+use crate::root::Root;
+
+/// What `src` remembers about a repository outside of its git directory.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RepoMetadata {
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+impl RepoMetadata {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+/// The sidecar metadata file, keyed by each repository's
+/// `<host>:<owner>/<name>` identity.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Metadata {
+    #[serde(flatten)]
+    pub repos: BTreeMap<String, RepoMetadata>,
+}
+
+impl Metadata {
+    #[must_use]
+    pub fn get(&self, repo: &Repo) -> Option<&RepoMetadata> {
+        self.repos.get(&repo.to_string())
+    }
+
+    pub fn get_mut(&mut self, repo: &Repo) -> &mut RepoMetadata {
+        self.repos.entry(repo.to_string()).or_default()
+    }
+
+    /// Carry what is remembered about the repository kept at `path` in `root`
+    /// over to `repo`, which it is now known as.
+    pub fn relocate(&mut self, root: &Root, path: &Path, repo: &Repo) {
+        let key = repo.to_string();
+
+        let previous = self
+            .repos
+            .keys()
+            .filter(|previous| **previous != key)
+            .find(|previous| {
+                parse_key(previous).is_some_and(|previous| {
+                    previous.managed_path(root) == path
+                })
+            })
+            .cloned();
+
+        if let Some(metadata) =
+            previous.and_then(|previous| self.repos.remove(&previous))
+        {
+            self.get_mut(repo).tags.extend(metadata.tags);
+        }
+    }
+
+    /// Drop entries that no longer hold anything.
+    fn prune(&mut self) {
+        self.repos.retain(|_, metadata| !metadata.is_empty());
+    }
+}
+
+/// The repository named by a `<host>:<owner>/<name>` key.
+fn parse_key(key: &str) -> Option<Repo> {
+    let (host, path) = key.split_once(':')?;
+
+    if host == LOCAL_HOST && !path.contains('/') {
+        return Some(Repo::new(host, "", path, None, ""));
+    }
+
+    let (owner, name) = path.rsplit_once('/')?;
+
+    Some(Repo::new(host, owner, name, None, ""))
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to determine the data directory
+pub fn get_metadata_path() -> Result<PathBuf, SrcRepoError> {
+    Ok(data_dir()
+        .ok_or(SrcRepoError::Metadata)?
+        .join("src/metadata.toml"))
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if the metadata file exists but can't be read or
+/// parsed
+pub fn read_metadata() -> Result<Metadata, SrcRepoError> {
+    let path = get_metadata_path()?;
+
+    if !path.exists() {
+        return Ok(Metadata::default());
+    }
+
+    toml::from_str(
+        &fs::read_to_string(path).map_err(|_| SrcRepoError::Metadata)?,
+    )
+    .map_err(|_| SrcRepoError::Metadata)
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to write the metadata file
+pub fn write_metadata(metadata: &mut Metadata) -> Result<(), SrcRepoError> {
+    let path = get_metadata_path()?;
+
+    metadata.prune();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|_| SrcRepoError::Metadata)?;
+    }
+
+    fs::write(
+        path,
+        toml::to_string(metadata).map_err(|_| SrcRepoError::Metadata)?,
+    )
+    .map_err(|_| SrcRepoError::Metadata)
+}
+
+/// Append a line to the relocation log, `relocations.log` next to the
+/// metadata file, recording that `repo` was moved from `from` to `to`.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to write the relocation log
+pub fn record_relocation(
+    repo: &Repo,
+    from: &Path,
+    to: &Path,
+) -> Result<(), SrcRepoError> {
+    let path = get_metadata_path()?.with_file_name("relocations.log");
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|_| SrcRepoError::Metadata)?;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|_| SrcRepoError::Metadata)?;
+
+    writeln!(
+        log,
+        "{timestamp}\t{repo}\t{}\t{}",
+        from.display(),
+        to.display()
+    )
+    .map_err(|_| SrcRepoError::Metadata)
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use shellexpand::tilde;
+use walkdir::WalkDir;
+
+use crate::config::Config;
+use crate::error::SrcRepoError;
+use crate::repo::Repo;
+
+/// The directory holding the bare mirrors that `add` clones through.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if `mirror_directory` is not set and the cache
+/// directory can't be determined
+pub fn get_mirror_directory(config: &Config) -> Result<PathBuf, SrcRepoError> {
+    let directory = config
+        .mirror_directory
+        .as_ref()
+        .ok_or(SrcRepoError::Config)?;
+
+    Ok(PathBuf::from(
+        tilde(&directory.to_string_lossy()).to_string(),
+    ))
+}
+
+/// Mirrors are kept at `<mirror_directory>/<host>/<owner>/<name>.git`.
+#[must_use]
+pub fn get_mirror_path(mirror_directory: &Path, repo: &Repo) -> PathBuf {
+    mirror_directory
+        .join(&repo.host)
+        .join(&repo.owner)
+        .join(format!("{}.git", repo.name))
+}
+
+/// The mirrors in `mirror_directory`, at `<host>/<namespace>/<name>.git`
+/// with namespaces of any depth.
+#[must_use]
+pub fn get_mirror_paths(mirror_directory: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![];
+    let mut entries = WalkDir::new(mirror_directory).min_depth(3).into_iter();
+
+    while let Some(entry) = entries.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+
+        if entry.file_type().is_dir()
+            && entry.file_name().to_string_lossy().ends_with(".git")
+        {
+            entries.skip_current_dir();
+            paths.push(entry.path().to_path_buf());
+        }
+    }
+
+    paths
+}
+
+/// The object directories that the repository at `path` borrows objects
+/// from, as listed in `.git/objects/info/alternates`.
+#[must_use]
+pub fn get_alternates(path: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(path.join(".git/objects/info/alternates"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn resolve(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The mirrors in `mirror_paths` that none of `repo_paths` borrow objects
+/// from.
+///
+/// Both sides are resolved before comparing, since git records alternates
+/// with symlinks resolved.
+#[must_use]
+pub fn get_unused_mirror_paths(
+    mirror_paths: &[PathBuf],
+    repo_paths: &[String],
+) -> Vec<PathBuf> {
+    let used: HashSet<PathBuf> = repo_paths
+        .iter()
+        .flat_map(|path| get_alternates(Path::new(path)))
+        .filter_map(|objects| objects.parent().map(resolve))
+        .collect();
+
+    mirror_paths
+        .iter()
+        .filter(|path| !used.contains(&resolve(path)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn it_keeps_mirrors_by_host_owner_and_name() {
+        let repo = Repo::new(
+            "github.com",
+            "tymbalodeon",
+            "src",
+            None,
+            "git@github.com:tymbalodeon/src.git",
+        );
+
+        assert_eq!(
+            get_mirror_path(Path::new("/cache/src/mirrors"), &repo),
+            PathBuf::from("/cache/src/mirrors/github.com/tymbalodeon/src.git")
+        );
+    }
+
+    #[test]
+    fn it_keeps_mirrors_used_through_a_symlinked_mirror_directory() {
+        let directory =
+            env::temp_dir().join(format!("src-mirrors-{}", process::id()));
+        let mirror_directory = directory.join("mirrors");
+        let link = directory.join("link");
+        let repo_path = directory.join("repo");
+
+        for path in ["github.com/me/used.git", "github.com/me/unused.git"] {
+            fs::create_dir_all(mirror_directory.join(path).join("objects"))
+                .unwrap();
+        }
+
+        fs::create_dir_all(repo_path.join(".git/objects/info")).unwrap();
+        std::os::unix::fs::symlink(&mirror_directory, &link).unwrap();
+
+        // As `git clone --reference` writes it, with the symlink resolved.
+        fs::write(
+            repo_path.join(".git/objects/info/alternates"),
+            format!(
+                "{}\n",
+                fs::canonicalize(&mirror_directory)
+                    .unwrap()
+                    .join("github.com/me/used.git/objects")
+                    .display()
+            ),
+        )
+        .unwrap();
+
+        let unused = get_unused_mirror_paths(
+            &get_mirror_paths(&link),
+            &[repo_path.to_string_lossy().to_string()],
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+
+        assert_eq!(unused, [link.join("github.com/me/unused.git")]);
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::Path;
+
+use git2::Repository;
+
+use crate::error::SrcRepoError;
+use crate::repo::{Repo, find_remote_name};
+
+/// A change to where repositories are hosted: a renamed owner (along with
+/// the namespaces nested in it), or a move to another host.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rename {
+    Owner {
+        from: String,
+        to: String,
+        host: Option<String>,
+    },
+
+    Host {
+        from: String,
+        to: String,
+    },
+}
+
+/// `owner` with the namespace `from` at its start replaced by `to`.
+fn rename_namespace(owner: &str, from: &str, to: &str) -> Option<String> {
+    if owner.eq_ignore_ascii_case(from) {
+        return Some(to.to_string());
+    }
+
+    let (namespace, rest) = owner.split_at_checked(from.len())?;
+
+    (namespace.eq_ignore_ascii_case(from) && rest.starts_with('/'))
+        .then(|| format!("{to}{rest}"))
+}
+
+impl Rename {
+    /// The new host and owner of a repository at `host` and `owner`, if this
+    /// rename affects it.
+    fn apply(&self, host: &str, owner: &str) -> Option<(String, String)> {
+        match self {
+            Self::Owner {
+                from,
+                to,
+                host: only_host,
+            } => {
+                if only_host.as_ref().is_some_and(|only_host| {
+                    !only_host.eq_ignore_ascii_case(host)
+                }) {
+                    return None;
+                }
+
+                Some((host.to_string(), rename_namespace(owner, from, to)?))
+            }
+
+            Self::Host { from, to } => host
+                .eq_ignore_ascii_case(from)
+                .then(|| (to.clone(), owner.to_string())),
+        }
+    }
+
+    /// What `repo` is called after this rename, if it affects it.
+    #[must_use]
+    pub fn rename_repo(&self, repo: &Repo) -> Option<Repo> {
+        if repo.is_local() {
+            return None;
+        }
+
+        let (host, owner) = self.apply(&repo.host, &repo.owner)?;
+
+        Some(Repo::new(&host, &owner, &repo.name, None, ""))
+    }
+
+    /// `url` pointing to where this rename moves it, if it affects it. Both
+    /// urls with a scheme and scp-like `user@host:path` urls are rewritten,
+    /// keeping everything but the host and owner as it is.
+    #[must_use]
+    pub fn rewrite_url(&self, url: &str) -> Option<String> {
+        let (host_start, host_end, path_start) =
+            if let Some(index) = url.find("://") {
+                let start = index + 3;
+                let path_start = start + url[start..].find('/')?;
+                let authority = &url[start..path_start];
+                let host_start =
+                    start + authority.rfind('@').map_or(0, |index| index + 1);
+                let host_end = host_start
+                    + url[host_start..path_start]
+                        .find(':')
+                        .unwrap_or(path_start - host_start);
+
+                (host_start, host_end, path_start)
+            } else {
+                let colon = url.find(':')?;
+                let host_start =
+                    url[..colon].rfind('@').map_or(0, |index| index + 1);
+
+                (host_start, colon, colon + 1)
+            };
+
+        let path = &url[path_start..];
+        let owner_start =
+            path_start + path.len() - path.trim_start_matches('/').len();
+        let owner_end = url.rfind('/').filter(|end| *end > owner_start)?;
+
+        let (host, owner) = self
+            .apply(&url[host_start..host_end], &url[owner_start..owner_end])?;
+
+        Some(format!(
+            "{}{host}{}{owner}{}",
+            &url[..host_start],
+            &url[host_end..owner_start],
+            &url[owner_end..]
+        ))
+    }
+}
+
+/// Point the remote that identifies the repository at `path` (see
+/// `remote_names`) to where `rename` moves it, returning the remote and its
+/// new url if `rename` affects it. With `dry_run`, only find it.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if the repository or its remote can't be read
+/// or updated
+pub fn rename_remote(
+    path: &Path,
+    remote_names: &[String],
+    rename: &Rename,
+    dry_run: bool,
+) -> Result<Option<(String, String)>, SrcRepoError> {
+    let repository = Repository::open(path)?;
+
+    let Some(name) = find_remote_name(&repository, remote_names)? else {
+        return Ok(None);
+    };
+
+    let remote = repository.find_remote(&name)?;
+    let url = remote.url().and_then(|url| rename.rewrite_url(url));
+    let push_url = remote.pushurl().and_then(|url| rename.rewrite_url(url));
+
+    if !dry_run {
+        if let Some(url) = &url {
+            repository.remote_set_url(&name, url)?;
+        }
+
+        if let Some(push_url) = &push_url {
+            repository.remote_set_pushurl(&name, Some(push_url))?;
+        }
+    }
+
+    Ok(url.map(|url| (name, url)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rewrites_urls_for_renamed_owners_and_hosts() {
+        let owner = Rename::Owner {
+            from: "old".to_string(),
+            to: "new".to_string(),
+            host: None,
+        };
+
+        let host = Rename::Host {
+            from: "github.com".to_string(),
+            to: "git.example.com".to_string(),
+        };
+
+        for (url, expected) in [
+            ("git@github.com:old/tool.git", "git@github.com:new/tool.git"),
+            ("https://github.com/Old/tool", "https://github.com/new/tool"),
+            (
+                "ssh://git@gitlab.com:2222/old/sub/tool.git",
+                "ssh://git@gitlab.com:2222/new/sub/tool.git",
+            ),
+        ] {
+            assert_eq!(owner.rewrite_url(url).unwrap(), expected);
+        }
+
+        assert_eq!(owner.rewrite_url("git@github.com:older/tool"), None);
+
+        assert_eq!(
+            host.rewrite_url("git@github.com:old/tool.git").unwrap(),
+            "git@git.example.com:old/tool.git"
+        );
+
+        assert_eq!(
+            host.rewrite_url("https://github.com/old/tool").unwrap(),
+            "https://git.example.com/old/tool"
+        );
+
+        assert_eq!(host.rewrite_url("https://gitlab.com/old/tool"), None);
+    }
+}
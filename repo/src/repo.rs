@@ -1,13 +1,21 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::Write;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use derivative::Derivative;
-use git_url_parse::{GitUrl, types::provider::GenericProvider};
+use git_url_parse::{GitUrl, GitUrlParseError};
 use git2::Repository;
 use shellexpand::tilde;
 
+use crate::alias::expand_alias;
 use crate::error::SrcRepoError;
+use crate::root::Root;
+
+/// Repositories without any remote are managed at `<root>/local/<name>`, and
+/// displayed as `local:<name>`.
+pub const LOCAL_HOST: &str = "local";
 
 #[derive(Clone, Debug, Derivative)]
 #[derivative(Eq, PartialEq, Hash)]
@@ -23,12 +31,25 @@ pub struct Repo {
     url: String,
 }
 
+/// Split the path of a git url into its namespace, which may be nested (as
+/// with GitLab subgroups), and its name.
+fn split_url_path(path: &str) -> Result<(&str, &str), SrcRepoError> {
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    path.rsplit_once('/')
+        .filter(|(owner, name)| !owner.is_empty() && !name.is_empty())
+        .ok_or(SrcRepoError::GitUrlParseError(
+            GitUrlParseError::ProviderUnsupported,
+        ))
+}
+
 fn parse_url(
     url: &str,
     local_source_path: Option<&PathBuf>,
 ) -> Result<Repo, SrcRepoError> {
     let git_url = GitUrl::parse(url)?;
-    let repo_provider = git_url.provider_info::<GenericProvider>()?;
+    let (owner, name) = split_url_path(git_url.path())?;
 
     let url = local_source_path.as_ref().map_or_else(
         || Ok::<String, SrcRepoError>(url.to_owned()),
@@ -37,16 +58,82 @@ fn parse_url(
 
     Ok(Repo {
         host: git_url.host().ok_or(SrcRepoError::GitUrl)?.to_string(),
-        owner: repo_provider.owner().clone(),
-        name: repo_provider.repo().clone(),
+        owner: owner.to_string(),
+        name: name.to_string(),
         local_source_path: local_source_path.cloned(),
         url,
     })
 }
+
+/// The name of the remote that identifies the repository: the first of
+/// `remote_names` that exists, or failing that, the only remote. `None` if
+/// the repository has no remotes.
+pub(crate) fn find_remote_name(
+    repository: &Repository,
+    remote_names: &[String],
+) -> Result<Option<String>, SrcRepoError> {
+    let remotes = repository.remotes()?;
+    let remotes: Vec<&str> = remotes.iter().flatten().collect();
+
+    let only_remote = match remotes.as_slice() {
+        [name] => Some(*name),
+        _ => None,
+    };
+
+    let name = remote_names
+        .iter()
+        .map(String::as_str)
+        .find(|name| remotes.contains(name))
+        .or(only_remote);
+
+    match name {
+        Some(name) => Ok(Some(name.to_string())),
+        None if remotes.is_empty() => Ok(None),
+        None => Err(SrcRepoError::Remote(remotes.join(", "))),
+    }
+}
+
+/// The name of the remote that identifies the repository at `path`, chosen
+/// by preference from `remote_names`, or `None` if it has no remotes.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if `path` is not a git repository, or it has
+/// several remotes and none of them is in `remote_names`
+pub fn get_remote_name(
+    path: &Path,
+    remote_names: &[String],
+) -> Result<Option<String>, SrcRepoError> {
+    find_remote_name(&Repository::open(path)?, remote_names)
+}
+
+/// The url of the remote that identifies the repository, or `None` if the
+/// repository has no remotes.
+fn get_remote_url(
+    repository: &Repository,
+    remote_names: &[String],
+) -> Result<Option<String>, SrcRepoError> {
+    find_remote_name(repository, remote_names)?
+        .map(|name| {
+            Ok(repository
+                .find_remote(&name)?
+                .url()
+                .ok_or(SrcRepoError::GitUrl)?
+                .to_owned())
+        })
+        .transpose()
+}
+
 impl Repo {
     #[must_use]
     pub fn display(&self, no_host: bool, no_owner: bool) -> String {
-        if no_host && no_owner {
+        if self.is_local() {
+            if no_host {
+                self.name.clone()
+            } else {
+                format!("{self}")
+            }
+        } else if no_host && no_owner {
             self.name.clone()
         } else if no_host {
             format!("{}/{}", self.owner, self.name)
@@ -57,40 +144,59 @@ impl Repo {
         }
     }
 
+    /// Parse `repo`, a git url or the path to a local repository. A local
+    /// repository is identified by the url of its remote, chosen by
+    /// preference from `remote_names`, or, if it has no remotes, as a local
+    /// repository.
+    ///
     /// # Errors
     ///
     /// Will return `SrcRepoError` if `repo` is a path but the path doesn't
     /// exist or it cannot determine a remote git url at that path.
-    pub fn from(repo: &str) -> Result<Self, SrcRepoError> {
-        let local_source_path = get_local_source_path(repo);
-
-        let url = local_source_path.as_ref().map_or_else(
-            || Ok::<String, SrcRepoError>(repo.to_string()),
-            |path| {
-                Ok(Repository::open(path)?
-                    .find_remote("origin")?
-                    .url()
-                    .ok_or(SrcRepoError::GitUrl)?
-                    .to_owned())
-            },
-        )?;
-
-        parse_url(&url, local_source_path.as_ref())
+    pub fn from(
+        repo: &str,
+        remote_names: &[String],
+    ) -> Result<Self, SrcRepoError> {
+        let Some(local_source_path) = get_local_source_path(repo) else {
+            return parse_url(repo, None);
+        };
+
+        match get_remote_url(
+            &Repository::open(&local_source_path)?,
+            remote_names,
+        )? {
+            Some(url) => parse_url(&url, Some(&local_source_path)),
+            None => Ok(Self::local(local_source_path)),
+        }
     }
 
+    /// A repository without remotes, at `path`.
     #[must_use]
-    pub fn managed_path(&self, root_directory: &str) -> PathBuf {
-        PathBuf::from(root_directory)
-            .join(&self.host)
-            .join(&self.owner)
-            .join(&self.name)
+    pub fn local(path: PathBuf) -> Self {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let url = path.to_string_lossy().to_string();
+
+        Self::new(LOCAL_HOST, "", &name, Some(path), &url)
     }
 
     #[must_use]
-    pub fn managed_path_name(&self, root_directory: &str) -> String {
-        self.managed_path(root_directory)
-            .to_string_lossy()
-            .to_string()
+    pub fn is_local(&self) -> bool {
+        self.host == LOCAL_HOST && self.owner.is_empty()
+    }
+
+    /// Where `root` keeps this repository, following its layout.
+    #[must_use]
+    pub fn managed_path(&self, root: &Root) -> PathBuf {
+        PathBuf::from(&root.path).join(root.layout.path(self))
+    }
+
+    #[must_use]
+    pub fn managed_path_name(&self, root: &Root) -> String {
+        self.managed_path(root).to_string_lossy().to_string()
     }
 
     #[must_use]
@@ -110,18 +216,115 @@ impl Repo {
         }
     }
 
+    /// The url to clone from. Shorthand like `host:owner/name` is expanded to
+    /// an ssh url; full urls are used as they are.
     #[must_use]
     pub fn url(self) -> String {
-        format!("git@{}", self.url)
+        if self.url.contains("://") || self.url.contains('@') {
+            self.url
+        } else {
+            format!("git@{}", self.url)
+        }
     }
 }
 
 impl fmt::Display for Repo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}:{}/{}", self.host, self.owner, self.name)
+        if self.is_local() {
+            write!(f, "{}:{}", self.host, self.name)
+        } else {
+            write!(f, "{}:{}/{}", self.host, self.owner, self.name)
+        }
     }
 }
 
+/// Path segments that start the part of a forge's web url showing something
+/// inside a repository, rather than the repository itself.
+const WEB_PATH_MARKERS: [&str; 9] = [
+    "-", "tree", "blob", "commit", "commits", "pull", "pulls", "issues",
+    "releases",
+];
+
+/// The segments of `path` naming a repository, without the segments of a web
+/// url pointing inside it, like `/tree/main/src` or GitLab's `/-/...`.
+fn strip_web_path(path: &str) -> Vec<&str> {
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    // Gitea and Forgejo show files at `/src/branch/<branch>/...`.
+    let end = (2..segments.len())
+        .find(|&index| {
+            WEB_PATH_MARKERS.contains(&segments[index])
+                || (segments[index] == "src"
+                    && segments.get(index + 1).is_some_and(|segment| {
+                        ["branch", "commit", "tag"].contains(segment)
+                    }))
+        })
+        .unwrap_or(segments.len());
+
+    segments[..end].to_vec()
+}
+
+/// Rewrite the spellings of a repository that people paste into the ones
+/// `parse_repos` understands.
+///
+/// Web urls lose the parts pointing inside the repository, query and
+/// fragment, `host/owner/name` (where the host has a `.`) becomes
+/// `host:owner/name`, and shorthand loses a trailing `.git`. Trailing slashes
+/// are dropped everywhere. Paths to existing directories (see
+/// `get_local_source_path`) are left alone.
+#[must_use]
+pub fn normalize_repo(repo: &str) -> String {
+    let repo = repo.trim();
+
+    if get_local_source_path(repo).is_some() {
+        return repo.to_string();
+    }
+
+    if let Some((scheme, rest)) = repo.split_once("://") {
+        if !["http", "https"].contains(&scheme.to_lowercase().as_str()) {
+            return repo.trim_end_matches('/').to_string();
+        }
+
+        let rest = rest.split(['?', '#']).next().unwrap_or_default();
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+
+        return format!(
+            "{scheme}://{host}/{}",
+            strip_web_path(path).join("/")
+        )
+        .trim_end_matches('/')
+        .to_string();
+    }
+
+    let (prefix, path) = match repo.split_once(':') {
+        Some((host, path)) if !host.contains('/') => {
+            (format!("{host}:"), path)
+        }
+
+        _ => match repo.split_once('/') {
+            Some((host, path)) if host.contains('.') && path.contains('/') => {
+                (format!("{host}:"), path)
+            }
+
+            _ => (String::new(), repo),
+        },
+    };
+
+    let path = strip_web_path(path).join("/");
+
+    // Urls like `git@host:owner/name.git` are used as they are.
+    if prefix.contains('@') {
+        return format!("{prefix}{path}");
+    }
+
+    format!("{prefix}{}", path.strip_suffix(".git").unwrap_or(&path))
+}
+
+/// Parse `repos`, after expanding `aliases` and normalizing their spelling,
+/// filling in the missing hosts and owners from the defaults or filters.
 #[must_use]
 pub fn parse_repos(
     repos: &[String],
@@ -129,23 +332,35 @@ pub fn parse_repos(
     default_owner: Option<&str>,
     host_filter: Option<&String>,
     owner_filter: Option<&String>,
+    remote_names: &[String],
+    aliases: &BTreeMap<String, String>,
 ) -> Vec<Result<Repo, SrcRepoError>> {
     repos
         .iter()
         .map(|repo| {
-            Repo::from(repo).map_or_else(
-                |_| {
-                    let mut owner: Option<&str> = None;
-                    let name: Option<&str>;
-                    let owner_separator = '/';
+            let repo = &normalize_repo(&expand_alias(repo, aliases));
 
-                    if repo.contains(owner_separator) {
-                        let mut components = repo.split(owner_separator);
+            Repo::from(repo, remote_names).map_or_else(
+                |error| {
+                    // A directory that isn't a repository is not shorthand.
+                    if get_local_source_path(repo).is_some() {
+                        return Err(error);
+                    }
 
-                        owner = components.next();
-                        name = components.next();
+                    let mut owner: Option<&str> = None;
+                    let name: Option<&str>;
+                    let path = repo
+                        .split_once(':')
+                        .map_or(repo.as_str(), |(_, path)| path);
+
+                    // Everything before the name is the owner, which may be
+                    // nested, as in `group/subgroup/name`.
+                    if let Some((namespace, repo_name)) = path.rsplit_once('/')
+                    {
+                        owner = Some(namespace);
+                        name = Some(repo_name);
                     } else {
-                        name = repo.split('/').next_back();
+                        name = Some(path);
                     }
 
                     match owner {
@@ -154,7 +369,7 @@ pub fn parse_repos(
                                 && owner.to_lowercase()
                                     != owner_filter.to_lowercase()
                                 {
-                                    return Err(SrcRepoError::Config);
+                                    return Err(SrcRepoError::Filter);
                                 }
                         }
 
@@ -182,7 +397,7 @@ pub fn parse_repos(
                                 && host.to_lowercase()
                                     != host_filter.to_lowercase()
                                 {
-                                    return Err(SrcRepoError::Config);
+                                    return Err(SrcRepoError::Filter);
                                 }
                         }
 
@@ -195,6 +410,15 @@ pub fn parse_repos(
                         }
                     }
 
+                    if host == Some(LOCAL_HOST) {
+                        let name = repo
+                            .strip_prefix(&format!("{LOCAL_HOST}:"))
+                            .or(name)
+                            .unwrap_or_default();
+
+                        return Ok(Repo::new(LOCAL_HOST, "", name, None, ""));
+                    }
+
                     let mut url: String = String::new();
 
                     if let Some(host) = host {
@@ -209,7 +433,7 @@ pub fn parse_repos(
                         let _ = write!(url, "{}", &format!("/{name}"));
                     }
 
-                    Repo::from(&url)
+                    Repo::from(&url, remote_names)
                 },
                 Ok,
             )
@@ -222,21 +446,49 @@ pub fn parse_repos(
         .collect()
 }
 
+/// Whether `repo` is spelled as a path: absolute, or starting with `.`, `..`
+/// or `~`. Anything else, like `name` or `owner/name`, is shorthand, even if
+/// a directory of that name happens to exist.
+fn is_path(repo: &str) -> bool {
+    Path::new(repo).is_absolute()
+        || [".", "..", "~"].contains(&repo)
+        || ["./", "../", "~/"]
+            .iter()
+            .any(|prefix| repo.starts_with(prefix))
+}
+
+/// The repository at `repo`, if it is the path of an existing directory: with
+/// `~` expanded, made absolute, with symlinks resolved, and moved up to the
+/// top level of the repository containing it.
 #[must_use]
 pub fn get_local_source_path(repo: &str) -> Option<PathBuf> {
-    let path = tilde(repo).to_string();
-    let path = Path::new(&path);
+    if !is_path(repo) {
+        return None;
+    }
 
-    if Path::is_dir(path) {
-        Some(path.to_owned())
-    } else {
-        None
+    let path = fs::canonicalize(tilde(repo).as_ref()).ok()?;
+
+    if !path.is_dir() {
+        return None;
     }
+
+    // A directory inside a repository stands for the whole repository.
+    Repository::discover(&path)
+        .ok()
+        .and_then(|repository| {
+            repository
+                .workdir()
+                .and_then(|workdir| fs::canonicalize(workdir).ok())
+        })
+        .or(Some(path))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{env, process};
+
     use super::*;
+    use crate::layout::Layout;
 
     const HOST: &str = "github.com";
     const NAME: &str = "src";
@@ -264,4 +516,174 @@ mod tests {
 
         validate_repo(&repo.unwrap(), url);
     }
+
+    #[test]
+    fn it_takes_only_explicit_paths_for_paths() {
+        for repo in ["/src/tool", ".", "..", "./tool", "../tool", "~/tool"] {
+            assert!(is_path(repo), "{repo}");
+        }
+
+        for repo in ["tool", "me/tool", "github.com:me/tool", ".tool", "~me"] {
+            assert!(!is_path(repo), "{repo}");
+        }
+    }
+
+    #[test]
+    fn it_resolves_local_paths_to_the_top_level() {
+        let directory = env::temp_dir()
+            .join(format!("src-local-source-path-{}", process::id()));
+        let subdirectory = directory.join("repo/sub");
+        let link = directory.join("link");
+
+        fs::create_dir_all(&subdirectory).unwrap();
+        Repository::init(directory.join("repo")).unwrap();
+        std::os::unix::fs::symlink(directory.join("repo"), &link).unwrap();
+
+        let top_level = fs::canonicalize(directory.join("repo")).unwrap();
+
+        for path in [subdirectory, link.join("sub"), link] {
+            assert_eq!(
+                get_local_source_path(&path.to_string_lossy()),
+                Some(top_level.clone())
+            );
+        }
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn it_normalizes_repo_spellings() {
+        for (repo, expected) in [
+            ("name", "name"),
+            ("owner/name", "owner/name"),
+            ("owner/name.git", "owner/name"),
+            ("owner/name/", "owner/name"),
+            ("github.com:owner/name", "github.com:owner/name"),
+            ("github.com:owner/name.git", "github.com:owner/name"),
+            ("github.com/owner/name", "github.com:owner/name"),
+            ("github.com/owner/name/", "github.com:owner/name"),
+            (
+                "gitlab.com/group/subgroup/name",
+                "gitlab.com:group/subgroup/name",
+            ),
+            (
+                "git@github.com:owner/name.git",
+                "git@github.com:owner/name.git",
+            ),
+            (
+                "https://github.com/owner/name",
+                "https://github.com/owner/name",
+            ),
+            (
+                "https://github.com/owner/name/",
+                "https://github.com/owner/name",
+            ),
+            (
+                "https://github.com/owner/name.git",
+                "https://github.com/owner/name.git",
+            ),
+            (
+                "https://github.com/owner/name/tree/main/src",
+                "https://github.com/owner/name",
+            ),
+            (
+                "https://github.com/owner/name/blob/main/README.md#usage",
+                "https://github.com/owner/name",
+            ),
+            (
+                "https://github.com/owner/name/pull/12",
+                "https://github.com/owner/name",
+            ),
+            (
+                "https://github.com/owner/name?tab=readme",
+                "https://github.com/owner/name",
+            ),
+            (
+                "https://gitlab.com/group/subgroup/name/-/tree/main",
+                "https://gitlab.com/group/subgroup/name",
+            ),
+            (
+                "https://codeberg.org/owner/name/src/branch/main/lib",
+                "https://codeberg.org/owner/name",
+            ),
+            (
+                "ssh://git@github.com/owner/name.git/",
+                "ssh://git@github.com/owner/name.git",
+            ),
+        ] {
+            assert_eq!(normalize_repo(repo), expected, "{repo}");
+        }
+    }
+
+    #[test]
+    fn it_parses_nested_namespaces() {
+        for url in [
+            "git@gitlab.example.com:group/subgroup/team/project.git",
+            "https://gitlab.example.com/group/subgroup/team/project",
+        ] {
+            let repo = parse_url(url, None).unwrap();
+
+            assert_eq!(repo.owner, "group/subgroup/team");
+            assert_eq!(repo.name, "project");
+
+            assert_eq!(
+                Layout::default().path(&repo),
+                PathBuf::from(
+                    "gitlab.example.com/group/subgroup/team/project"
+                )
+            );
+        }
+
+        let repos = parse_repos(
+            &["subgroup/team/project".to_string()],
+            Some("gitlab.example.com"),
+            Some("group"),
+            None,
+            None,
+            &[],
+            &BTreeMap::new(),
+        );
+
+        assert_eq!(
+            repos[0].as_ref().unwrap().to_string(),
+            "gitlab.example.com:subgroup/team/project"
+        );
+    }
+
+    #[test]
+    fn it_prefers_remotes_by_name_then_the_only_remote() {
+        let path = std::env::temp_dir()
+            .join(format!("src-remotes-{}", std::process::id()));
+        let repository = Repository::init(&path).unwrap();
+        let remote_names = vec!["origin".to_string()];
+        let from = || Repo::from(&path.to_string_lossy(), &remote_names);
+
+        let local_repo = from().unwrap();
+
+        assert!(local_repo.is_local());
+        assert_eq!(
+            local_repo.to_string(),
+            format!("local:{}", local_repo.name)
+        );
+
+        repository
+            .remote("fork", "git@github.com:me/src.git")
+            .unwrap();
+
+        assert_eq!(from().unwrap().owner, "me");
+
+        repository
+            .remote("mirror", "git@gitlab.com:me/src.git")
+            .unwrap();
+
+        assert!(matches!(from(), Err(SrcRepoError::Remote(_))));
+
+        repository
+            .remote("origin", "git@github.com:tymbalodeon/src.git")
+            .unwrap();
+
+        validate_repo(&from().unwrap(), &path.to_string_lossy());
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
 }
@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use shellexpand::tilde;
+
+use crate::config::Config;
+use crate::error::SrcRepoError;
+use crate::layout::Layout;
+use crate::repo::Repo;
+
+pub const DEFAULT_ROOT_NAME: &str = "default";
+
+/// A root directory as written in the config file: either just a path, or a
+/// path with rules deciding which repositories are added to it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RootConfig {
+    Path(PathBuf),
+
+    Rules {
+        path: PathBuf,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        hosts: Vec<String>,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        owners: Vec<String>,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Root {
+    pub name: String,
+    pub path: String,
+    pub hosts: Vec<String>,
+    pub owners: Vec<String>,
+    pub layout: Layout,
+}
+
+/// Expand `~` in `path`, and resolve symlinks if it exists, so that it can be
+/// compared with the canonical paths of local repositories.
+fn expand(path: &Path) -> String {
+    let path = tilde(&path.to_string_lossy()).to_string();
+
+    fs::canonicalize(&path)
+        .map_or(path, |path| path.to_string_lossy().to_string())
+}
+
+fn contains_case_insensitive(values: &[String], value: &str) -> bool {
+    values
+        .iter()
+        .any(|candidate| candidate.to_lowercase() == value.to_lowercase())
+}
+
+impl Root {
+    #[must_use]
+    pub fn new(name: &str, config: &RootConfig, layout: &Layout) -> Self {
+        let (path, hosts, owners) = match config {
+            RootConfig::Path(path) => (path, vec![], vec![]),
+
+            RootConfig::Rules {
+                path,
+                hosts,
+                owners,
+            } => (path, hosts.clone(), owners.clone()),
+        };
+
+        Self {
+            name: name.to_string(),
+            path: expand(path),
+            hosts,
+            owners,
+            layout: layout.clone(),
+        }
+    }
+
+    #[must_use]
+    pub const fn has_rules(&self) -> bool {
+        !self.hosts.is_empty() || !self.owners.is_empty()
+    }
+
+    /// Whether `repo` satisfies this root's rules. A root without rules
+    /// matches nothing, so that it only receives repositories by default.
+    #[must_use]
+    pub fn matches(&self, repo: &Repo) -> bool {
+        self.has_rules()
+            && (self.hosts.is_empty()
+                || contains_case_insensitive(&self.hosts, &repo.host))
+            && (self.owners.is_empty()
+                || contains_case_insensitive(&self.owners, &repo.owner))
+    }
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if `name` is given but no root has that name, if
+/// no root directory is configured, or if the layout is invalid
+pub fn get_roots(
+    config: &Config,
+    name: Option<&String>,
+) -> Result<Vec<Root>, SrcRepoError> {
+    let layout = Layout::new(&config.layout, config.host.as_deref())?;
+
+    let roots: Vec<Root> = if config.roots.is_empty() {
+        vec![Root::new(
+            DEFAULT_ROOT_NAME,
+            &RootConfig::Path(
+                config.root_directory.clone().ok_or(SrcRepoError::Config)?,
+            ),
+            &layout,
+        )]
+    } else {
+        config
+            .roots
+            .iter()
+            .map(|(name, root)| Root::new(name, root, &layout))
+            .collect()
+    };
+
+    match name {
+        Some(name) => {
+            let roots: Vec<Root> = roots
+                .into_iter()
+                .filter(|root| &root.name == name)
+                .collect();
+
+            if roots.is_empty() {
+                Err(SrcRepoError::Root(name.clone()))
+            } else {
+                Ok(roots)
+            }
+        }
+
+        None => Ok(roots),
+    }
+}
+
+/// Choose the root that `repo` should be added to: the first root whose rules
+/// match it, otherwise the first root without rules, otherwise the first root.
+#[must_use]
+pub fn root_for<'a>(roots: &'a [Root], repo: &Repo) -> Option<&'a Root> {
+    roots
+        .iter()
+        .find(|root| root.matches(repo))
+        .or_else(|| roots.iter().find(|root| !root.has_rules()))
+        .or_else(|| roots.first())
+}
+
+/// Choose the root that `repo`, already kept in `current`, should stay in:
+/// `current`, unless its rules don't match `repo` and another root's do.
+#[must_use]
+pub fn root_for_managed<'a>(
+    roots: &'a [Root],
+    current: &'a Root,
+    repo: &Repo,
+) -> &'a Root {
+    if current.matches(repo) {
+        return current;
+    }
+
+    roots
+        .iter()
+        .find(|root| root.matches(repo))
+        .unwrap_or(current)
+}
+
+/// Find where `repo` is already managed, if it is managed in any of `roots`.
+#[must_use]
+pub fn find_managed_path(roots: &[Root], repo: &Repo) -> Option<PathBuf> {
+    roots
+        .iter()
+        .map(|root| repo.managed_path(root))
+        .find(|path| path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(host: &str, owner: &str) -> Repo {
+        Repo::new(host, owner, "src", None, "")
+    }
+
+    #[test]
+    fn it_places_repos_by_rules_before_falling_back() {
+        let roots = vec![
+            Root::new(
+                "personal",
+                &RootConfig::Path(PathBuf::from("/src")),
+                &Layout::default(),
+            ),
+            Root::new(
+                "work",
+                &RootConfig::Rules {
+                    path: PathBuf::from("/work"),
+                    hosts: vec!["gitlab.example.com".to_string()],
+                    owners: vec![],
+                },
+                &Layout::default(),
+            ),
+        ];
+
+        assert_eq!(
+            root_for(&roots, &repo("gitlab.example.com", "team"))
+                .map(|root| root.name.as_str()),
+            Some("work")
+        );
+
+        assert_eq!(
+            root_for(&roots, &repo("github.com", "tymbalodeon"))
+                .map(|root| root.name.as_str()),
+            Some("personal")
+        );
+    }
+
+    #[test]
+    fn it_keeps_managed_repos_in_their_root_unless_a_rule_moves_them() {
+        let roots: Vec<Root> = ["/src", "/other"]
+            .into_iter()
+            .map(|path| {
+                Root::new(
+                    path,
+                    &RootConfig::Path(PathBuf::from(path)),
+                    &Layout::default(),
+                )
+            })
+            .chain([Root::new(
+                "work",
+                &RootConfig::Rules {
+                    path: PathBuf::from("/work"),
+                    hosts: vec!["gitlab.example.com".to_string()],
+                    owners: vec![],
+                },
+                &Layout::default(),
+            )])
+            .collect();
+
+        assert_eq!(
+            root_for_managed(&roots, &roots[1], &repo("github.com", "me"))
+                .name,
+            "/other"
+        );
+
+        assert_eq!(
+            root_for_managed(
+                &roots,
+                &roots[1],
+                &repo("gitlab.example.com", "team")
+            )
+            .name,
+            "work"
+        );
+    }
+}
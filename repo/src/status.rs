@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use git2::{BranchType, Repository, StatusOptions};
+
+use crate::error::SrcRepoError;
+use crate::fork::get_fork_status;
+use crate::manifest::get_current_branch;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RepoStatus {
+    /// The checked out branch, if any
+    pub branch: Option<String>,
+
+    /// Commits on the branch and not on its upstream, if it has one
+    pub ahead: Option<usize>,
+
+    /// Commits on the upstream of the branch and not on the branch
+    pub behind: Option<usize>,
+
+    /// Commits on the default branch of the `upstream` remote and not on the
+    /// local one, for forks
+    pub behind_upstream: Option<usize>,
+
+    /// Whether there are uncommitted changes, ignoring submodules
+    pub dirty: bool,
+}
+
+impl RepoStatus {
+    /// Describe the status, as in `main [ahead 1, behind 2] dirty`.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let mut description =
+            self.branch.as_deref().unwrap_or("detached").to_string();
+
+        let counts: Vec<String> = [
+            ("ahead", self.ahead),
+            ("behind", self.behind),
+            ("behind upstream", self.behind_upstream),
+        ]
+        .into_iter()
+        .filter_map(|(label, count)| {
+            count
+                .filter(|count| *count > 0)
+                .map(|count| format!("{label} {count}"))
+        })
+        .collect();
+
+        if !counts.is_empty() {
+            description = format!("{description} [{}]", counts.join(", "));
+        }
+
+        if self.dirty {
+            description.push_str(" dirty");
+        }
+
+        description
+    }
+}
+
+/// # Errors
+///
+/// Will return `SrcRepoError` if `repo_path` is not a git repository
+pub fn get_repo_status(
+    repo_path: &Path,
+    remote_names: &[String],
+) -> Result<RepoStatus, SrcRepoError> {
+    let repository = Repository::open(repo_path)?;
+    let branch = get_current_branch(&repository);
+
+    let (ahead, behind) = branch
+        .as_deref()
+        .and_then(|branch| {
+            let local =
+                repository.find_branch(branch, BranchType::Local).ok()?;
+            let upstream = local.upstream().ok()?;
+
+            repository
+                .graph_ahead_behind(
+                    local.get().target()?,
+                    upstream.get().target()?,
+                )
+                .ok()
+        })
+        .map_or((None, None), |(ahead, behind)| (Some(ahead), Some(behind)));
+
+    let dirty = !repository
+        .statuses(Some(
+            StatusOptions::new()
+                .include_untracked(true)
+                .exclude_submodules(true),
+        ))?
+        .is_empty();
+
+    Ok(RepoStatus {
+        branch,
+        ahead,
+        behind,
+        behind_upstream: get_fork_status(repo_path, remote_names)?
+            .and_then(|fork_status| fork_status.behind),
+        dirty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_describes_only_what_differs() {
+        let status = RepoStatus {
+            branch: Some("main".to_string()),
+            ahead: Some(0),
+            behind: Some(2),
+            behind_upstream: Some(5),
+            dirty: true,
+        };
+
+        assert_eq!(
+            status.describe(),
+            "main [behind 2, behind upstream 5] dirty"
+        );
+        assert_eq!(RepoStatus::default().describe(), "detached");
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::{Repository, SubmoduleIgnore, SubmoduleStatus};
+
+use crate::error::SrcRepoError;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Submodule {
+    /// The path of the submodule, relative to the top-level repository
+    pub path: PathBuf,
+
+    pub url: Option<String>,
+
+    /// Whether the submodule is checked out
+    pub initialized: bool,
+
+    /// Whether a commit other than the recorded one is checked out
+    pub moved: bool,
+
+    /// Whether the submodule has uncommitted changes
+    pub dirty: bool,
+}
+
+/// Whether the repository at `path` is a submodule checkout, whose `.git` is
+/// a file pointing into the `modules` directory of its parent.
+#[must_use]
+pub fn is_submodule_path(path: &Path) -> bool {
+    fs::read_to_string(path.join(".git")).is_ok_and(|git_file| {
+        git_file
+            .strip_prefix("gitdir:")
+            .is_some_and(|git_dir| git_dir.contains("/modules/"))
+    })
+}
+
+fn collect_submodules(
+    repository: &Repository,
+    prefix: &Path,
+    submodules: &mut Vec<Submodule>,
+) -> Result<(), SrcRepoError> {
+    for submodule in repository.submodules()? {
+        let path = prefix.join(submodule.path());
+        let status = submodule
+            .name()
+            .and_then(|name| {
+                repository
+                    .submodule_status(name, SubmoduleIgnore::None)
+                    .ok()
+            })
+            .unwrap_or_else(SubmoduleStatus::empty);
+
+        submodules.push(Submodule {
+            path: path.clone(),
+            url: submodule.url().map(ToString::to_string),
+            initialized: !status.contains(SubmoduleStatus::WD_UNINITIALIZED),
+            moved: status.contains(SubmoduleStatus::WD_MODIFIED),
+            dirty: status.intersects(
+                SubmoduleStatus::WD_INDEX_MODIFIED
+                    | SubmoduleStatus::WD_WD_MODIFIED
+                    | SubmoduleStatus::WD_UNTRACKED,
+            ),
+        });
+
+        if let Ok(nested) = submodule.open() {
+            collect_submodules(&nested, &path, submodules)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// List the submodules of the repository at `repo_path`, including nested
+/// ones, in order.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if `repo_path` is not a git repository
+pub fn get_submodules(
+    repo_path: &Path,
+) -> Result<Vec<Submodule>, SrcRepoError> {
+    let mut submodules = vec![];
+
+    collect_submodules(
+        &Repository::open(repo_path)?,
+        Path::new(""),
+        &mut submodules,
+    )?;
+
+    Ok(submodules)
+}
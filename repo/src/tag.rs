@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+use crate::config::Config;
+use crate::error::SrcRepoError;
+use crate::metadata::{Metadata, read_metadata};
+use crate::repo::Repo;
+
+/// Whether `repo` falls under `pattern`, which can be a host
+/// (`github.com`), a host and owner (`github.com:owner`) or a full repository
+/// (`github.com:owner/name`).
+#[must_use]
+pub fn matches_pattern(repo: &Repo, pattern: &str) -> bool {
+    let repo = repo.to_string().to_lowercase();
+    let pattern = pattern.to_lowercase();
+
+    repo.strip_prefix(&pattern).is_some_and(|rest| {
+        rest.is_empty() || rest.starts_with(':') || rest.starts_with('/')
+    })
+}
+
+/// Tags added with `src tag add` together with those assigned by the rules in
+/// the config's `[tags]` table.
+#[must_use]
+pub fn get_tags(
+    config: &Config,
+    metadata: &Metadata,
+    repo: &Repo,
+) -> BTreeSet<String> {
+    let mut tags = metadata
+        .get(repo)
+        .map(|metadata| metadata.tags.clone())
+        .unwrap_or_default();
+
+    for (tag, patterns) in &config.tags {
+        if patterns
+            .iter()
+            .any(|pattern| matches_pattern(repo, pattern))
+        {
+            tags.insert(tag.clone());
+        }
+    }
+
+    tags
+}
+
+#[must_use]
+pub fn has_tag(
+    config: &Config,
+    metadata: &Metadata,
+    repo: &Repo,
+    tag: &str,
+) -> bool {
+    get_tags(config, metadata, repo).contains(tag)
+}
+
+/// Keep only the repositories with `tag`, or all of them if `tag` is `None`.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if it fails to read the metadata file
+pub fn filter_by_tag(
+    config: &Config,
+    repos: Vec<Repo>,
+    tag: Option<&String>,
+) -> Result<Vec<Repo>, SrcRepoError> {
+    let Some(tag) = tag else {
+        return Ok(repos);
+    };
+
+    let metadata = read_metadata()?;
+
+    Ok(repos
+        .into_iter()
+        .filter(|repo| has_tag(config, &metadata, repo, tag))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_matches_hosts_owners_and_repos() {
+        let repo = Repo::new("github.com", "our-org", "tool", None, "");
+
+        assert!(matches_pattern(&repo, "github.com"));
+        assert!(matches_pattern(&repo, "github.com:our-org"));
+        assert!(matches_pattern(&repo, "GitHub.com:our-org/tool"));
+        assert!(!matches_pattern(&repo, "github.com:our"));
+        assert!(!matches_pattern(&repo, "gitlab.com"));
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+
+use crate::error::SrcRepoError;
+use crate::manifest::get_current_branch;
+
+/// Worktrees of the managed repository at `<path>` are kept in
+/// `<path>.worktrees/<branch>`.
+pub const WORKTREES_SUFFIX: &str = ".worktrees";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Worktree {
+    /// The checked out branch, if any
+    pub branch: Option<String>,
+    pub path: PathBuf,
+}
+
+/// The directory holding the worktrees of the repository at `repo_path`.
+#[must_use]
+pub fn get_worktrees_path(repo_path: &Path) -> PathBuf {
+    let mut name = repo_path.file_name().unwrap_or_default().to_os_string();
+
+    name.push(WORKTREES_SUFFIX);
+
+    repo_path.with_file_name(name)
+}
+
+#[must_use]
+pub fn get_worktree_path(repo_path: &Path, branch: &str) -> PathBuf {
+    get_worktrees_path(repo_path).join(branch)
+}
+
+#[must_use]
+pub fn is_worktrees_path(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(WORKTREES_SUFFIX))
+}
+
+/// List the linked worktrees of the repository at `repo_path`, wherever they
+/// are.
+///
+/// # Errors
+///
+/// Will return `SrcRepoError` if `repo_path` is not a git repository
+pub fn get_worktrees(repo_path: &Path) -> Result<Vec<Worktree>, SrcRepoError> {
+    let repository = Repository::open(repo_path)?;
+    let mut worktrees = vec![];
+
+    for name in repository.worktrees()?.iter().flatten() {
+        let path = repository.find_worktree(name)?.path().to_path_buf();
+
+        let branch = Repository::open(&path)
+            .ok()
+            .and_then(|worktree| get_current_branch(&worktree));
+
+        worktrees.push(Worktree { branch, path });
+    }
+
+    worktrees.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(worktrees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_worktrees_next_to_their_repo() {
+        let repo_path = Path::new("/home/me/src/github.com/me/tool");
+        let worktree_path = get_worktree_path(repo_path, "feature/x");
+
+        assert_eq!(
+            worktree_path,
+            PathBuf::from(
+                "/home/me/src/github.com/me/tool.worktrees/feature/x"
+            )
+        );
+
+        assert!(is_worktrees_path(&get_worktrees_path(repo_path)));
+        assert!(!is_worktrees_path(repo_path));
+    }
+}
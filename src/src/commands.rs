@@ -1,6 +1,21 @@
 pub mod add;
+pub mod archive;
+pub mod backup;
 pub mod cd;
 pub mod config;
+pub mod doctor;
+pub mod exec;
+pub mod export;
 pub mod hook;
+pub mod import;
 pub mod list;
+pub mod mirror;
+pub mod relayout;
+pub mod relocate;
 pub mod remove;
+pub mod rename;
+pub mod status;
+pub mod sync;
+pub mod tag;
+pub mod unshallow;
+pub mod worktree;
@@ -1,12 +1,24 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::slice;
 
-use anyhow::Result;
-use repo::config::get_config;
-use repo::repo::Repo;
-use repo::{config::get_root_directory, list::get_managed_repo_paths};
+use anyhow::{Result, bail};
+use dirs::home_dir;
+use repo::clone::{CloneConfig, CloneFilter, get_clone_config};
+use repo::config::{Config, get_config};
+use repo::error::SrcRepoError;
+use repo::forge::{RemoteRepoFilter, Visibility, get_forge};
+use repo::fork::{UPSTREAM_REMOTE, has_upstream_remote};
+use repo::mirror::{get_mirror_directory, get_mirror_path};
+use repo::repo::{Repo, parse_repos};
+use repo::root::{Root, find_managed_path, get_roots, root_for};
 
 use crate::commands::remove::remove_repo;
+use crate::error::{Failures, SrcError};
+use crate::git::git;
+use crate::log::{LogLevel, log};
 use crate::repo::parse_repos_with_error_log;
 
 fn filter_unique_repos(repos: &[Repo]) -> Vec<Repo> {
@@ -38,54 +50,439 @@ fn filter_unique_repos(repos: &[Repo]) -> Vec<Repo> {
     repos_to_add
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Default)]
+pub enum VisibilityOption {
+    #[default]
+    All,
+    Public,
+    Private,
+}
+
+impl From<VisibilityOption> for Visibility {
+    fn from(visibility: VisibilityOption) -> Self {
+        match visibility {
+            VisibilityOption::All => Self::All,
+            VisibilityOption::Public => Self::Public,
+            VisibilityOption::Private => Self::Private,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum CloneFilterOption {
+    #[value(name = "blob:none")]
+    BlobNone,
+
+    #[value(name = "tree:0")]
+    TreeZero,
+}
+
+impl From<CloneFilterOption> for CloneFilter {
+    fn from(filter: CloneFilterOption) -> Self {
+        match filter {
+            CloneFilterOption::BlobNone => Self::BlobNone,
+            CloneFilterOption::TreeZero => Self::TreeZero,
+        }
+    }
+}
+
+/// List the repositories of `owner` on the forge at `host`.
+fn get_forge_repos(
+    config: &Config,
+    host: &str,
+    owner: &str,
+    filter: RemoteRepoFilter,
+) -> Result<Vec<Repo>> {
+    let remote_repos = get_forge(config, host)?.list_repos(owner)?;
+    let total = remote_repos.len();
+
+    let repos: Vec<Repo> = remote_repos
+        .into_iter()
+        .filter(|remote_repo| filter.matches(remote_repo))
+        .map(|remote_repo| {
+            Repo::new(
+                host,
+                &remote_repo.owner,
+                &remote_repo.name,
+                None,
+                &remote_repo.url,
+            )
+        })
+        .collect();
+
+    log(
+        &LogLevel::Info,
+        &format!(
+            "Found {total} repositories for {owner} on {host}, adding {}",
+            repos.len()
+        ),
+    );
+
+    Ok(repos)
+}
+
+/// Point the `upstream` remote of the fork `repo`, at `path`, to `upstream`
+/// (`<host>:<owner>/<name>`, defaulting to the host of `repo`), or to the
+/// repository the forge says it was forked from.
+fn add_upstream(
+    config: &Config,
+    repo: &Repo,
+    path: &Path,
+    upstream: Option<&String>,
+) -> Result<()> {
+    let url = if let Some(upstream) = upstream {
+        parse_repos(
+            slice::from_ref(upstream),
+            Some(&repo.host),
+            None,
+            None,
+            None,
+            &config.remote_names,
+            &config.aliases,
+        )
+        .into_iter()
+        .next()
+        .ok_or(SrcRepoError::GitUrl)??
+        .url()
+    } else {
+        get_forge(config, &repo.host)?
+            .get_parent(&repo.owner, &repo.name)?
+            .ok_or_else(|| anyhow::anyhow!("{repo} is not a fork"))?
+            .url
+    };
+
+    let path_name = path.to_string_lossy();
+
+    if has_upstream_remote(path) {
+        git(&["-C", &path_name, "remote", "set-url", UPSTREAM_REMOTE, &url])?;
+    } else {
+        git(&["-C", &path_name, "remote", "add", UPSTREAM_REMOTE, &url])?;
+    }
+
+    git(&["-C", &path_name, "fetch", UPSTREAM_REMOTE])?;
+    git(&[
+        "-C",
+        &path_name,
+        "remote",
+        "set-head",
+        UPSTREAM_REMOTE,
+        "--auto",
+    ])?;
+
+    log(
+        &LogLevel::Info,
+        &format!("Set the {UPSTREAM_REMOTE} of {repo} to {url}"),
+    );
+
+    Ok(())
+}
+
 pub fn add(
     repos: &[String],
+    root: Option<&String>,
     host: Option<&String>,
     owner: Option<&String>,
     me: bool,
+    all_from: Option<&String>,
+    filter: RemoteRepoFilter,
+    clone_config: CloneConfig,
+    upstream: Option<&String>,
+    detect_upstream: bool,
+    mirror: bool,
     force: bool,
 ) -> Result<()> {
+    if let Some(upstream) = upstream
+        && repos.len() != 1
+    {
+        return Err(SrcError::Usage(format!(
+            "--upstream {upstream} needs exactly one repository to add"
+        ))
+        .into());
+    }
+
     let config = get_config()?;
+    let clone_options = CloneOptions::new(&config, clone_config, mirror)?;
     let owner = if me { config.owner.as_ref() } else { owner };
-    let repos =
-        parse_repos_with_error_log(&config, repos, host, owner, false)?;
-    let root_directory = get_root_directory()?;
-    let repo_paths = get_managed_repo_paths(&root_directory);
+    let roots = get_roots(&config, None)?;
+    let target_roots = get_roots(&config, root)?;
+
+    let mut failures = Failures::default();
+
+    let repos = if let Some(all_from) = all_from {
+        let host =
+            host.or(config.host.as_ref()).ok_or(SrcRepoError::Config)?;
+
+        get_forge_repos(&config, host, all_from, filter)?
+    } else {
+        parse_repos_with_error_log(
+            &config,
+            &roots,
+            repos,
+            host,
+            owner,
+            false,
+            Some(&mut failures),
+        )?
+    };
 
     for repo in filter_unique_repos(&repos) {
-        let managed_path = repo.managed_path_name(&root_directory);
-
-        if let Some(ref local_source_path) = repo.local_source_path {
-            if force || !repo_paths.contains(&managed_path) {
-                println!(
-                    "Moving {} to {managed_path}",
-                    local_source_path.to_string_lossy()
-                );
-
-                Command::new("mv")
-                    .args(vec![
-                        &local_source_path.to_string_lossy().to_string(),
-                        &format!(
-                            "{root_directory}/{}/{}",
-                            repo.host, repo.owner
-                        ),
-                    ])
-                    .status()?;
-            }
-        } else if force || !repo_paths.contains(&managed_path) {
-            if force && Path::new(&managed_path).exists() {
-                remove_repo(&managed_path)?;
-            }
+        failures.record(
+            add_repo(&repo, &roots, &target_roots, &clone_options, force)
+                .and_then(|added| {
+                    let (Added::New(path) | Added::Existing(path)) = added;
 
-            Command::new("git")
-                .args(vec!["clone", &repo.url(), &managed_path])
-                .status()?;
+                    if upstream.is_some() || detect_upstream {
+                        add_upstream(&config, &repo, &path, upstream)
+                    } else {
+                        Ok(())
+                    }
+                }),
+        );
+    }
+
+    failures.finish()
+}
+
+/// How `add_repo` clones repositories.
+#[derive(Default)]
+pub struct CloneOptions {
+    /// Settings that take precedence over `defaults`
+    pub clone_config: CloneConfig,
+
+    /// Settings by host or `<host>:<owner>/<name>`, from the config
+    pub defaults: BTreeMap<String, CloneConfig>,
+
+    /// Clone through bare mirrors in this directory
+    pub mirror_directory: Option<PathBuf>,
+
+    /// Clone submodules unless the settings say otherwise
+    pub submodules: bool,
+}
+
+impl CloneOptions {
+    /// # Errors
+    ///
+    /// Will return `SrcRepoError` if mirrors are enabled, by `mirror` or the
+    /// config, but the mirror directory can't be determined
+    pub fn new(
+        config: &Config,
+        clone_config: CloneConfig,
+        mirror: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            clone_config,
+            defaults: config.clone_defaults.clone(),
+            mirror_directory: if mirror || config.mirror {
+                Some(get_mirror_directory(config)?)
+            } else {
+                None
+            },
+            submodules: config.submodules,
+        })
+    }
+}
+
+fn clone(url: &str, path: &str, clone_config: &CloneConfig) -> Result<()> {
+    let clone_args = clone_config.args();
+    let mut args = vec!["clone"];
+
+    args.extend(clone_args.iter().map(String::as_str));
+    args.extend([url, path]);
+
+    git(&args)
+}
+
+/// Clone `url` to `path` from the mirror at `mirror_path`, borrowing its
+/// objects, then point `origin` back at `url`. The mirror is created if it
+/// doesn't exist, and updated first if it does; if updating fails (for
+/// example, when offline), the clone is made from the mirror as it is.
+///
+/// Submodules are cloned from their own remotes once `origin` is set, so
+/// that relative submodule urls resolve against `url`.
+fn clone_through_mirror(
+    url: &str,
+    mirror_path: &Path,
+    path: &str,
+    clone_config: &CloneConfig,
+) -> Result<()> {
+    let mirror = mirror_path.to_string_lossy();
+
+    if mirror_path.exists() {
+        if let Err(error) =
+            git(&["-C", &mirror, "remote", "update", "--prune"])
+        {
+            log(
+                &LogLevel::Warning,
+                &format!("{error}; cloning from the mirror as it is"),
+            );
         }
+    } else {
+        git(&["clone", "--mirror", url, &mirror])?;
+    }
+
+    let recurse_submodules = clone_config.recurse_submodules == Some(true);
+
+    let clone_config = CloneConfig {
+        recurse_submodules: None,
+        ..clone_config.clone()
+    };
+
+    let clone_args = clone_config.args();
+
+    // Local clones ignore `--depth` and `--filter` unless made over `file://`,
+    // which in turn only filters if the mirror allows it.
+    let source = if clone_config.is_partial() {
+        format!("file://{mirror}")
+    } else {
+        mirror.to_string()
+    };
+
+    let mut args = vec!["-c", "uploadpack.allowFilter=true", "clone"];
+
+    args.extend(clone_args.iter().map(String::as_str));
+    args.extend(["--reference", &mirror, &source, path]);
+
+    git(&args)?;
+    git(&["-C", path, "remote", "set-url", "origin", url])?;
+
+    if recurse_submodules {
+        git(&["-C", path, "submodule", "update", "--init", "--recursive"])?;
     }
 
     Ok(())
 }
 
+pub enum Added {
+    /// The repository was cloned or moved to this path
+    New(PathBuf),
+
+    /// The repository was already managed at this path, so it was left alone
+    Existing(PathBuf),
+}
+
+/// Clone or move `repo` into the root chosen from `target_roots`, unless it is
+/// already managed in one of `roots` (and `force` is not set).
+///
+/// # Errors
+///
+/// Will return an error if moving, removing or cloning the repository fails
+pub fn add_repo(
+    repo: &Repo,
+    roots: &[Root],
+    target_roots: &[Root],
+    clone_options: &CloneOptions,
+    force: bool,
+) -> Result<Added> {
+    if let Some(local_source_path) = &repo.local_source_path {
+        if roots
+            .iter()
+            .any(|root| local_source_path.starts_with(&root.path))
+        {
+            log(
+                &LogLevel::Info,
+                &format!(
+                    "{repo} is already managed at {}",
+                    local_source_path.display()
+                ),
+            );
+
+            return Ok(Added::Existing(local_source_path.clone()));
+        }
+
+        if home_dir().is_some_and(|home_dir| &home_dir == local_source_path) {
+            bail!("refusing to move the home directory");
+        }
+    }
+
+    let existing_path = find_managed_path(roots, repo);
+
+    if let Some(existing_path) = &existing_path
+        && !force
+    {
+        if let Some(local_source_path) = &repo.local_source_path {
+            return Err(SrcError::Usage(format!(
+                "{repo} is already managed at {}; use --force to replace it \
+                 with {}",
+                existing_path.display(),
+                local_source_path.display()
+            ))
+            .into());
+        }
+
+        log(
+            &LogLevel::Debug,
+            &format!(
+                "{repo} is already managed at {}",
+                existing_path.display()
+            ),
+        );
+
+        return Ok(Added::Existing(existing_path.clone()));
+    }
+
+    let root = root_for(target_roots, repo).ok_or(SrcRepoError::Config)?;
+    let managed_path = repo.managed_path_name(root);
+
+    if let Some(ref local_source_path) = repo.local_source_path {
+        let local_source_path =
+            local_source_path.to_string_lossy().to_string();
+
+        log(
+            &LogLevel::Info,
+            &format!("Moving {local_source_path} to {managed_path}"),
+        );
+
+        // Otherwise `mv` would move the repository into the existing one.
+        if let Some(existing_path) = &existing_path {
+            remove_repo(&existing_path.to_string_lossy())?;
+        }
+
+        if let Some(parent) = repo.managed_path(root).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if !Command::new("mv")
+            .args(vec![&local_source_path, &managed_path])
+            .status()?
+            .success()
+        {
+            bail!("failed to move {local_source_path} to {managed_path}");
+        }
+    } else {
+        if repo.is_local() {
+            bail!("{repo} has no remote to clone it from");
+        }
+
+        if let Some(existing_path) = existing_path {
+            remove_repo(&existing_path.to_string_lossy())?;
+        }
+
+        let url = repo.clone().url();
+        let clone_config = get_clone_config(
+            &clone_options.defaults,
+            repo,
+            &clone_options.clone_config,
+        )
+        .or(&CloneConfig {
+            recurse_submodules: Some(clone_options.submodules),
+            ..CloneConfig::default()
+        });
+
+        if let Some(mirror_directory) = &clone_options.mirror_directory {
+            clone_through_mirror(
+                &url,
+                &get_mirror_path(mirror_directory, repo),
+                &managed_path,
+                &clone_config,
+            )?;
+        } else {
+            clone(&url, &managed_path, &clone_config)?;
+        }
+    }
+
+    Ok(Added::New(PathBuf::from(managed_path)))
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
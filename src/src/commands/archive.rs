@@ -0,0 +1,209 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use repo::archive::{
+    ArchivedRepo, archive_info, archive_repo, get_archive_directory,
+    get_archive_path, get_archived_repos, remove_archive, restore_repo,
+};
+use repo::config::{Config, get_config};
+use repo::mirror::get_alternates;
+use repo::root::{Root, get_roots};
+use repo::worktree::{get_worktrees, get_worktrees_path};
+
+use crate::commands::relayout::remove_empty_parents;
+use crate::error::{Failures, SrcError};
+use crate::git::git;
+use crate::log::{LogLevel, log};
+use crate::repo::{find_managed_repo_path, parse_repos_with_error_log};
+
+/// Copy the objects that the repository at `path` borrows from a mirror into
+/// it, so that the archive doesn't depend on the mirror, which `mirror gc
+/// --unused` would otherwise delete.
+fn dissociate(path: &Path) -> Result<()> {
+    if get_alternates(path).is_empty() {
+        return Ok(());
+    }
+
+    git(&["-C", &path.to_string_lossy(), "repack", "-a", "-d"])?;
+    fs::remove_file(path.join(".git/objects/info/alternates"))?;
+
+    Ok(())
+}
+
+fn archive_one(
+    config: &Config,
+    roots: &[Root],
+    archive_directory: &Path,
+    repo: &str,
+) -> Result<()> {
+    let (repo, path) = find_managed_repo_path(config, roots, repo)?;
+
+    if !get_worktrees(&path)?.is_empty() {
+        bail!("{repo} has worktrees; remove them before archiving it");
+    }
+
+    let archive_path = get_archive_path(archive_directory, &repo);
+
+    if archive_path.exists() {
+        bail!("{repo} is already archived at {}", archive_path.display());
+    }
+
+    dissociate(&path)?;
+    archive_repo(&archive_path, &archive_info(&repo, &path))?;
+    fs::remove_dir_all(&path)?;
+
+    let worktrees_path = get_worktrees_path(&path);
+
+    if worktrees_path.exists() {
+        fs::remove_dir(worktrees_path)?;
+    }
+
+    if let Some(root) = roots.iter().find(|root| path.starts_with(&root.path))
+    {
+        remove_empty_parents(&path, Path::new(&root.path));
+    }
+
+    log(
+        &LogLevel::Info,
+        &format!("Archived {repo} to {}", archive_path.display()),
+    );
+
+    Ok(())
+}
+
+/// Pack managed repositories into the archive directory and remove them
+/// from their root.
+pub fn archive(repos: &[String], root: Option<&String>) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, root)?;
+    let archive_directory = get_archive_directory(&config)?;
+    let mut failures = Failures::default();
+
+    for repo in repos {
+        failures.record(archive_one(
+            &config,
+            &roots,
+            &archive_directory,
+            repo,
+        ));
+    }
+
+    failures.finish()
+}
+
+/// Find the archived repository matching `repo`: the one it names, or else
+/// the only one whose `<owner>/<name>` ends with it.
+fn find_archived_repo(
+    config: &Config,
+    roots: &[Root],
+    archived_repos: &[ArchivedRepo],
+    repo: &str,
+) -> Result<ArchivedRepo> {
+    let candidates = parse_repos_with_error_log(
+        config,
+        roots,
+        &[repo.to_string()],
+        None,
+        None,
+        false,
+        None,
+    )?;
+
+    if let Some(archived) = archived_repos
+        .iter()
+        .find(|archived| candidates.contains(&archived.info.repo()))
+    {
+        return Ok(archived.clone());
+    }
+
+    let suffix = format!("/{}", repo.trim_matches('/'));
+
+    let matches: Vec<&ArchivedRepo> = archived_repos
+        .iter()
+        .filter(|archived| {
+            format!("/{}/{}", archived.info.owner, archived.info.name)
+                .ends_with(&suffix)
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [archived] => Ok((*archived).clone()),
+
+        [] => Err(SrcError::Usage(format!(
+            "no archived repository matches {repo:?}"
+        ))
+        .into()),
+
+        _ => {
+            let matches: Vec<String> = matches
+                .iter()
+                .map(|archived| archived.info.repo().to_string())
+                .collect();
+
+            Err(SrcError::Usage(format!(
+                "{repo:?} matches more than one archived repository: {}",
+                matches.join(", ")
+            ))
+            .into())
+        }
+    }
+}
+
+fn unarchive_one(
+    config: &Config,
+    roots: &[Root],
+    archive_directory: &Path,
+    repo: &str,
+) -> Result<()> {
+    let archived = find_archived_repo(
+        config,
+        roots,
+        &get_archived_repos(archive_directory),
+        repo,
+    )?;
+
+    let repo = archived.info.repo();
+    let path = &archived.info.path;
+
+    if path.exists() {
+        bail!(
+            "can't restore {repo} to {}: it already exists",
+            path.display()
+        );
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    restore_repo(&archived.archive_path, path)?;
+    remove_archive(&archived.archive_path)?;
+    remove_empty_parents(&archived.archive_path, archive_directory);
+
+    log(
+        &LogLevel::Info,
+        &format!("Restored {repo} to {}", path.display()),
+    );
+
+    Ok(())
+}
+
+/// Restore archived repositories to where they were archived from.
+pub fn unarchive(repos: &[String]) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, None)?;
+    let archive_directory = get_archive_directory(&config)?;
+    let mut failures = Failures::default();
+
+    for repo in repos {
+        failures.record(unarchive_one(
+            &config,
+            &roots,
+            &archive_directory,
+            repo,
+        ));
+    }
+
+    failures.finish()
+}
@@ -0,0 +1,240 @@
+use std::fs;
+use std::path::{self, Path};
+
+use anyhow::{Result, bail};
+use repo::backup::{
+    BackupEntry, BackupIndex, backup_entry, read_backup_index,
+    write_backup_index,
+};
+use repo::config::{Config, get_config};
+use repo::repo::Repo;
+use repo::root::{Root, get_roots, root_for};
+
+use crate::error::{Failures, SrcError};
+use crate::git::git;
+use crate::log::{LogLevel, log};
+use crate::repo::select_managed_repos;
+
+fn backup_repo(
+    directory: &Path,
+    roots: &[Root],
+    repo: &Repo,
+    path: &Path,
+    index: &mut BackupIndex,
+) -> Result<()> {
+    let root = roots
+        .iter()
+        .find(|root| path.starts_with(&root.path))
+        .map(|root| root.name.as_str());
+    let entry = backup_entry(repo, root, path)?;
+
+    // git refuses to bundle a repository without commits, so only its
+    // remotes are kept.
+    let Some(bundle) = &entry.bundle else {
+        log(
+            &LogLevel::Info,
+            &format!("Recording {repo}, which is empty"),
+        );
+        index.insert(entry);
+
+        return Ok(());
+    };
+
+    let bundle_path = directory.join(bundle);
+
+    if let Some(parent) = bundle_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    log(&LogLevel::Info, &format!("Bundling {repo}"));
+
+    git(&[
+        "-C",
+        &path.to_string_lossy(),
+        "bundle",
+        "create",
+        &bundle_path.to_string_lossy(),
+        "--all",
+    ])?;
+
+    index.insert(entry);
+
+    Ok(())
+}
+
+/// Write a git bundle with every ref of each selected managed repository to
+/// `directory`, and list them with their remotes in its index.
+pub fn backup(
+    directory: &str,
+    repos: &[String],
+    root: Option<&String>,
+    tag: Option<&String>,
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, root)?;
+
+    // git runs in each repository, so a relative path would resolve there.
+    let directory = path::absolute(directory)?;
+
+    fs::create_dir_all(&directory)?;
+
+    let mut index = read_backup_index(&directory)?;
+    let mut failures = Failures::default();
+
+    for (repo, path) in
+        select_managed_repos(&config, &roots, repos, tag, &mut failures)?
+    {
+        failures
+            .record(backup_repo(&directory, &roots, &repo, &path, &mut index));
+    }
+
+    write_backup_index(&directory, &index)?;
+
+    failures.finish()
+}
+
+/// Restore `entry` to `selected_root`, or else to the root it was backed up
+/// from, or, if that root no longer exists, to the one the rules choose.
+fn restore_repo(
+    config: &Config,
+    roots: &[Root],
+    selected_root: Option<&Root>,
+    directory: &Path,
+    entry: &BackupEntry,
+) -> Result<()> {
+    let repo = entry.repo();
+
+    let root = selected_root
+        .or_else(|| {
+            entry
+                .root
+                .as_ref()
+                .and_then(|name| roots.iter().find(|root| &root.name == name))
+        })
+        .or_else(|| root_for(roots, &repo));
+
+    let Some(root) = root else {
+        return Err(SrcError::Usage("no root directory".to_string()).into());
+    };
+
+    let target = repo.managed_path(root);
+
+    if target.exists() {
+        bail!(
+            "can't restore {repo} to {}: it already exists",
+            target.display()
+        );
+    }
+
+    log(
+        &LogLevel::Info,
+        &format!("Restoring {repo} to {}", target.display()),
+    );
+
+    let target_name = target.to_string_lossy();
+
+    git(&["init", "--quiet", &target_name])?;
+
+    let result = (|| {
+        if let Some(bundle) = &entry.bundle {
+            git(&[
+                "-C",
+                &target_name,
+                "fetch",
+                "--quiet",
+                "--update-head-ok",
+                &directory.join(bundle).to_string_lossy(),
+                "+refs/*:refs/*",
+            ])?;
+        }
+
+        for (name, url) in &entry.remotes {
+            git(&["-C", &target_name, "remote", "add", name, url])?;
+        }
+
+        match &entry.head {
+            Some(head) if entry.bundle.is_some() => {
+                let head = head.strip_prefix("refs/heads/").unwrap_or(head);
+
+                git(&[
+                    "-C",
+                    &target_name,
+                    "-c",
+                    "advice.detachedHead=false",
+                    "checkout",
+                    "--quiet",
+                    head,
+                    "--",
+                ])?;
+            }
+
+            // Without commits, there is only the name of the unborn branch.
+            Some(head) => {
+                git(&["-C", &target_name, "symbolic-ref", "HEAD", head])?;
+            }
+
+            None => {}
+        }
+
+        for (branch, upstream) in &entry.upstreams {
+            git(&[
+                "-C",
+                &target_name,
+                "branch",
+                "--quiet",
+                &format!("--set-upstream-to={upstream}"),
+                branch,
+            ])?;
+        }
+
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&target);
+    }
+
+    if config.submodules && target.join(".gitmodules").exists() {
+        log(
+            &LogLevel::Info,
+            &format!("Run `src sync {repo}` to check out its submodules"),
+        );
+    }
+
+    result
+}
+
+/// Recreate the managed repositories backed up in `directory` from their
+/// bundles, with their remotes configured as they were.
+pub fn restore_backup(directory: &str, root: Option<&String>) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, None)?;
+    let selected_root = root
+        .map(|root| get_roots(&config, Some(root)))
+        .transpose()?
+        .and_then(|roots| roots.into_iter().next());
+    let directory = path::absolute(directory)?;
+    let index = read_backup_index(&directory)?;
+
+    if index.repos.is_empty() {
+        return Err(SrcError::Usage(format!(
+            "no backup found in {}",
+            directory.display()
+        ))
+        .into());
+    }
+
+    let mut failures = Failures::default();
+
+    for entry in &index.repos {
+        failures.record(restore_repo(
+            &config,
+            &roots,
+            selected_root.as_ref(),
+            &directory,
+            entry,
+        ));
+    }
+
+    failures.finish()
+}
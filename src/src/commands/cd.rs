@@ -1,5 +1,5 @@
-use crate::log::{LogLevel, log};
+use anyhow::{Result, bail};
 
-pub fn cd() {
-    log(&LogLevel::Error, "The shell hook has not been initialized.");
+pub fn cd() -> Result<()> {
+    bail!("The shell hook has not been initialized.");
 }
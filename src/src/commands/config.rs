@@ -2,9 +2,9 @@ use std::env;
 use std::process::Command;
 
 use anyhow::Result;
-use repo::config::{get_config, get_config_path};
+use repo::config::{get_config, get_config_path, get_profile_names};
 
-use crate::log::{LogLevel, log};
+use crate::error::SrcError;
 
 pub fn config() -> Result<()> {
     print!("{}", toml::to_string(&get_config()?)?);
@@ -24,18 +24,39 @@ pub fn get_config_value(key: &str) -> Result<()> {
     let config = get_config()?;
 
     let value = match key {
+        "archive_directory" => config
+            .archive_directory
+            .map_or(String::new(), |value| value.display().to_string()),
+
         "host" => config.host.map_or(String::new(), |value| value),
 
+        "layout" => config.layout,
+
+        "log_file" => config
+            .log_file
+            .map_or(String::new(), |value| value.display().to_string()),
+
+        "mirror" => config.mirror.to_string(),
+
+        "mirror_directory" => config
+            .mirror_directory
+            .map_or(String::new(), |value| value.display().to_string()),
+
         "owner" => config.owner.map_or(String::new(), |value| value),
 
+        "profile" => config.profile.map_or(String::new(), |value| value),
+
         "root_directory" => config
             .root_directory
             .map_or(String::new(), |value| value.display().to_string()),
 
-        _ => {
-            log(&LogLevel::Error, &format!("key {key:?} does not exist"));
+        "submodules" => config.submodules.to_string(),
 
-            String::new()
+        _ => {
+            return Err(SrcError::Usage(format!(
+                "key {key:?} does not exist"
+            ))
+            .into());
         }
     };
 
@@ -43,3 +64,9 @@ pub fn get_config_value(key: &str) -> Result<()> {
 
     Ok(())
 }
+
+pub fn profiles() -> Result<()> {
+    println!("{}", get_profile_names()?.join("\n"));
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+use inquire::{Confirm, InquireError};
+use repo::config::get_config;
+use repo::doctor::{Problem, audit};
+use repo::root::{Root, get_roots};
+
+use crate::commands::relayout::move_repo;
+use crate::log::{LogLevel, log};
+
+fn confirm(message: &str) -> Result<bool> {
+    Ok(Confirm::new(message).with_default(true).prompt()?)
+}
+
+/// Offer the safe repair for `problem`, if it has one. Returns whether the
+/// problem was fixed.
+fn fix(problem: &Problem, roots: &[Root]) -> Result<bool> {
+    match problem {
+        Problem::Empty(path) => {
+            if !confirm(&format!(
+                "Remove the empty directory {}?",
+                path.display().to_string().cyan()
+            ))? {
+                return Ok(false);
+            }
+
+            fs::remove_dir_all(path)?;
+        }
+
+        Problem::Misplaced {
+            repo,
+            path,
+            expected,
+        } => {
+            let Some(root) =
+                roots.iter().find(|root| path.starts_with(&root.path))
+            else {
+                return Ok(false);
+            };
+
+            if !confirm(&format!(
+                "Move {repo} to {}?",
+                expected.display().to_string().cyan()
+            ))? {
+                return Ok(false);
+            }
+
+            move_repo(path, expected, Path::new(&root.path))?;
+        }
+
+        // Nothing can tell which files or clones are safe to delete.
+        Problem::Stray(_)
+        | Problem::Broken { .. }
+        | Problem::Duplicate { .. } => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Report problems in the managed trees, and, with `fix`, offer to repair
+/// the ones that can be repaired safely.
+pub fn doctor(root: Option<&String>, fix_problems: bool) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, root)?;
+    let problems = audit(&config, &roots);
+    let mut remaining = 0;
+
+    for problem in &problems {
+        println!("{problem}");
+
+        if fix_problems {
+            match fix(problem, &roots) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                // The prompt was cancelled, or can't be shown.
+                Err(error) if error.is::<InquireError>() => return Err(error),
+
+                Err(error) => log(&LogLevel::Error, &error.to_string()),
+            }
+        }
+
+        remaining += 1;
+    }
+
+    match remaining {
+        0 => {}
+        1 => bail!("found 1 problem in the managed tree"),
+        _ => bail!("found {remaining} problems in the managed tree"),
+    }
+
+    if problems.is_empty() {
+        log(&LogLevel::Info, "No problems found.");
+    }
+
+    Ok(())
+}
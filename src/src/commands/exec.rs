@@ -0,0 +1,182 @@
+use std::fmt::Write as _;
+use std::io::{Write, stderr, stdout};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use repo::config::get_config;
+use repo::list::get_managed_repos;
+use repo::repo::Repo;
+use repo::root::get_roots;
+
+use crate::error::Failures;
+use crate::log::{LogLevel, log};
+
+fn prefix_lines(label: &str, output: &[u8]) -> String {
+    let label = label.cyan();
+
+    String::from_utf8_lossy(output).lines().fold(
+        String::new(),
+        |mut lines, line| {
+            let _ = writeln!(lines, "{label}: {line}");
+
+            lines
+        },
+    )
+}
+
+fn check_status(
+    command: &[String],
+    label: &str,
+    status: ExitStatus,
+) -> Result<()> {
+    if !status.success() {
+        bail!("`{}` failed in {label} ({status})", command.join(" "));
+    }
+
+    Ok(())
+}
+
+/// Run `command` in `path`, then print its output all at once so that it
+/// isn't interleaved with the output of other repositories.
+fn run_captured(
+    command: &[String],
+    path: &Path,
+    label: &str,
+    prefix: bool,
+    output_lock: &Mutex<()>,
+) -> Result<()> {
+    let output = Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(path)
+        .output()
+        .with_context(|| {
+            format!("failed to run `{}` in {label}", command.join(" "))
+        })?;
+
+    let _lock = output_lock.lock();
+
+    if prefix {
+        print!("{}", prefix_lines(label, &output.stdout));
+        eprint!("{}", prefix_lines(label, &output.stderr));
+    } else {
+        println!("{}", label.cyan().bold());
+
+        let _ = stdout().write_all(&output.stdout);
+        let _ = stderr().write_all(&output.stderr);
+    }
+
+    check_status(command, label, output.status)
+}
+
+/// Run `command` in `path` with the terminal attached.
+fn run_inherited(command: &[String], path: &Path, label: &str) -> Result<()> {
+    println!("{}", label.cyan().bold());
+
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(path)
+        .status()
+        .with_context(|| {
+            format!("failed to run `{}` in {label}", command.join(" "))
+        })?;
+
+    check_status(command, label, status)
+}
+
+/// Run `command` in each of `repos`, `jobs` at a time, returning the results
+/// in the same order as `repos`.
+fn run_parallel(
+    command: &[String],
+    repos: &[(Repo, PathBuf)],
+    jobs: usize,
+    prefix: bool,
+) -> Vec<Result<()>> {
+    let next = AtomicUsize::new(0);
+    let output_lock = Mutex::new(());
+    let results: Mutex<Vec<(usize, Result<()>)>> = Mutex::new(vec![]);
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, repos.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+
+                    let Some((repo, path)) = repos.get(index) else {
+                        break;
+                    };
+
+                    let result = run_captured(
+                        command,
+                        path,
+                        &repo.to_string(),
+                        prefix,
+                        &output_lock,
+                    );
+
+                    if let Ok(mut results) = results.lock() {
+                        results.push((index, result));
+                    }
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_default();
+
+    results.sort_by_key(|(index, _)| *index);
+
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+pub fn exec(
+    root: Option<&String>,
+    host: Option<&String>,
+    owner: Option<&String>,
+    name: Option<&String>,
+    tag: Option<&String>,
+    me: bool,
+    jobs: usize,
+    prefix: bool,
+    command: &[String],
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, root)?;
+
+    let repos =
+        get_managed_repos(&config, &roots, host, owner, name, tag, me)?;
+
+    log(
+        &LogLevel::Debug,
+        &format!(
+            "running `{}` in {} repositories",
+            command.join(" "),
+            repos.len()
+        ),
+    );
+
+    let results: Vec<Result<()>> = if jobs <= 1 && !prefix {
+        repos
+            .iter()
+            .map(|(repo, path)| {
+                run_inherited(command, path, &repo.to_string())
+            })
+            .collect()
+    } else {
+        run_parallel(command, &repos, jobs, prefix)
+    };
+
+    // Recorded once every command has finished, so that the failures are
+    // listed together at the end.
+    let mut failures = Failures::default();
+
+    for result in results {
+        failures.record(result);
+    }
+
+    failures.finish()
+}
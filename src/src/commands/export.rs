@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use repo::config::get_config;
+use repo::manifest::{Manifest, create_manifest};
+use repo::metadata::read_metadata;
+use repo::root::get_roots;
+
+use crate::log::{LogLevel, log};
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum ManifestFormat {
+    Json,
+    Toml,
+}
+
+impl ManifestFormat {
+    /// Use `format` if given, otherwise guess from the extension of `path`,
+    /// falling back to TOML.
+    pub fn resolve(format: Option<Self>, path: Option<&Path>) -> Self {
+        format.unwrap_or_else(|| {
+            if path.and_then(Path::extension).is_some_and(|extension| {
+                extension.eq_ignore_ascii_case("json")
+            }) {
+                Self::Json
+            } else {
+                Self::Toml
+            }
+        })
+    }
+}
+
+pub fn serialize_manifest(
+    manifest: &Manifest,
+    format: ManifestFormat,
+) -> Result<String> {
+    Ok(match format {
+        ManifestFormat::Json => serde_json::to_string_pretty(manifest)? + "\n",
+        ManifestFormat::Toml => toml::to_string(manifest)?,
+    })
+}
+
+pub fn export(
+    root: Option<&String>,
+    tag: Option<&String>,
+    format: Option<ManifestFormat>,
+    output: Option<&PathBuf>,
+) -> Result<()> {
+    let config = get_config()?;
+    let mut manifest = create_manifest(
+        &config,
+        &read_metadata()?,
+        &get_roots(&config, root)?,
+    );
+
+    if let Some(tag) = tag {
+        manifest.repos.retain(|repo| repo.tags.contains(tag));
+    }
+
+    let contents = serialize_manifest(
+        &manifest,
+        ManifestFormat::resolve(format, output.map(PathBuf::as_path)),
+    )?;
+
+    if let Some(output) = output {
+        fs::write(output, contents)?;
+
+        log(
+            &LogLevel::Info,
+            &format!(
+                "Exported {} repositories to {}",
+                manifest.repos.len(),
+                output.display()
+            ),
+        );
+    } else {
+        print!("{contents}");
+    }
+
+    Ok(())
+}
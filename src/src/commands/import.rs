@@ -0,0 +1,323 @@
+use std::fs;
+use std::path::{self, Path, PathBuf};
+
+use anyhow::Result;
+use repo::clone::CloneConfig;
+use repo::config::{Config, get_config};
+use repo::error::SrcRepoError;
+use repo::foreign::{
+    ForeignRepo, get_ghq_root, get_gita_path, get_mrconfig_path, read_ghq,
+    read_gita, read_mrconfig,
+};
+use repo::manifest::{Manifest, ManifestRepo};
+use repo::metadata::{Metadata, read_metadata, write_metadata};
+use repo::repo::Repo;
+use repo::root::{Root, find_managed_path, get_roots};
+use repo::tag::get_tags;
+
+use crate::commands::add::{Added, CloneOptions, add_repo};
+use crate::commands::export::ManifestFormat;
+use crate::error::{Failures, SrcError};
+use crate::git::git;
+use crate::log::{LogLevel, log};
+
+fn read_manifest(path: &Path) -> Result<Manifest> {
+    let contents = fs::read_to_string(path)?;
+
+    Ok(match ManifestFormat::resolve(None, Some(path)) {
+        ManifestFormat::Json => serde_json::from_str(&contents)?,
+        ManifestFormat::Toml => toml::from_str(&contents)?,
+    })
+}
+
+fn warn_on_error(result: Result<()>) {
+    if let Err(error) = result {
+        log(&LogLevel::Warning, &error.to_string());
+    }
+}
+
+/// Bring a freshly cloned repository in line with its manifest entry: add
+/// the other remotes, check out the recorded branch and restore its tags.
+fn restore(
+    config: &Config,
+    metadata: &mut Metadata,
+    entry: &ManifestRepo,
+    path: &Path,
+) {
+    let path = path.to_string_lossy();
+
+    for (name, url) in &entry.remotes {
+        if name != "origin" {
+            warn_on_error(git(&["-C", &path, "remote", "add", name, url]));
+        }
+    }
+
+    if let Some(branch) = &entry.branch {
+        warn_on_error(git(&["-C", &path, "checkout", "--quiet", branch]));
+    }
+
+    let repo = entry.repo();
+    let rule_tags = get_tags(config, &Metadata::default(), &repo);
+
+    metadata
+        .get_mut(&repo)
+        .tags
+        .extend(entry.tags.difference(&rule_tags).cloned());
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Default)]
+pub enum ImportSource {
+    /// A manifest written by `src export`
+    #[default]
+    Manifest,
+
+    /// A ghq root directory (defaults to `ghq root`)
+    Ghq,
+
+    /// A myrepos config file (defaults to `~/.mrconfig`)
+    Mr,
+
+    /// A gita `repos.csv` file (defaults to gita's config directory)
+    Gita,
+}
+
+/// What an import did, or would do, reported once it is finished.
+#[derive(Default)]
+struct Summary {
+    added: Vec<String>,
+    skipped: Vec<String>,
+    unmapped: Vec<String>,
+}
+
+impl Summary {
+    fn skip(&mut self, repo: &Repo, path: &Path) {
+        self.skipped.push(format!("{repo} ({})", path.display()));
+    }
+
+    fn log(&self, dry_run: bool) {
+        let verb = if dry_run { "Would add" } else { "Added" };
+
+        if self.added.is_empty() {
+            log(&LogLevel::Info, &format!("{verb} 0 repositories."));
+        } else {
+            log(
+                &LogLevel::Info,
+                &format!(
+                    "{verb} {} repositories: {}",
+                    self.added.len(),
+                    self.added.join(", ")
+                ),
+            );
+        }
+
+        if !self.skipped.is_empty() {
+            log(
+                &LogLevel::Info,
+                &format!(
+                    "Skipped {} already managed repositories: {}",
+                    self.skipped.len(),
+                    self.skipped.join(", ")
+                ),
+            );
+        }
+
+        if !self.unmapped.is_empty() {
+            log(
+                &LogLevel::Warning,
+                &format!(
+                    "Couldn't map {} entries to repositories: {}",
+                    self.unmapped.len(),
+                    self.unmapped.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+/// Clone every repository in `manifest` that is not managed yet.
+pub fn import_repos(
+    config: &Config,
+    roots: &[Root],
+    target_roots: &[Root],
+    clone_options: &CloneOptions,
+    manifest: &Manifest,
+    dry_run: bool,
+) -> Result<()> {
+    let mut metadata = read_metadata()?;
+    let mut failures = Failures::default();
+    let mut summary = Summary::default();
+
+    for entry in &manifest.repos {
+        let repo = entry.repo();
+
+        if dry_run {
+            if let Some(path) = find_managed_path(roots, &repo) {
+                summary.skip(&repo, &path);
+            } else {
+                summary.added.push(repo.to_string());
+            }
+
+            continue;
+        }
+
+        failures.record(
+            add_repo(&repo, roots, target_roots, clone_options, false).map(
+                |result| match result {
+                    Added::New(path) => {
+                        restore(config, &mut metadata, entry, &path);
+                        summary.added.push(repo.to_string());
+                    }
+
+                    Added::Existing(path) => summary.skip(&repo, &path),
+                },
+            ),
+        );
+    }
+
+    if !dry_run {
+        write_metadata(&mut metadata)?;
+    }
+
+    summary.log(dry_run);
+
+    failures.finish()
+}
+
+fn describe(foreign_repo: &ForeignRepo) -> String {
+    foreign_repo
+        .path
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .or_else(|| foreign_repo.url.clone())
+        .unwrap_or_default()
+}
+
+/// Resolve an entry from another repository manager, preferring an existing
+/// clone to its url.
+fn resolve(
+    foreign_repo: &ForeignRepo,
+    remote_names: &[String],
+) -> Result<Repo, SrcRepoError> {
+    foreign_repo.existing_path().map_or_else(
+        || {
+            foreign_repo
+                .url
+                .as_ref()
+                .map_or(Err(SrcRepoError::Import), |url| {
+                    Repo::from(url, remote_names)
+                })
+        },
+        |path| {
+            let path =
+                path::absolute(path).map_err(|_| SrcRepoError::Import)?;
+
+            Repo::from(&path.to_string_lossy(), remote_names)
+        },
+    )
+}
+
+/// Adopt the existing clones listed by another repository manager into the
+/// managed layout, and clone those that are missing.
+fn import_foreign_repos(
+    config: &Config,
+    roots: &[Root],
+    target_roots: &[Root],
+    clone_options: &CloneOptions,
+    foreign_repos: &[ForeignRepo],
+    dry_run: bool,
+) -> Result<()> {
+    let mut failures = Failures::default();
+    let mut summary = Summary::default();
+
+    for foreign_repo in foreign_repos {
+        let repo = match resolve(foreign_repo, &config.remote_names) {
+            Ok(repo) => repo,
+
+            Err(error) => {
+                summary
+                    .unmapped
+                    .push(format!("{} ({error})", describe(foreign_repo)));
+
+                continue;
+            }
+        };
+
+        if dry_run {
+            if let Some(path) = find_managed_path(roots, &repo) {
+                summary.skip(&repo, &path);
+            } else {
+                summary.added.push(repo.to_string());
+            }
+
+            continue;
+        }
+
+        failures.record(
+            add_repo(&repo, roots, target_roots, clone_options, false).map(
+                |result| match result {
+                    Added::New(_) => summary.added.push(repo.to_string()),
+                    Added::Existing(path) => summary.skip(&repo, &path),
+                },
+            ),
+        );
+    }
+
+    summary.log(dry_run);
+
+    failures.finish()
+}
+
+pub fn import(
+    source: Option<&PathBuf>,
+    from: ImportSource,
+    root: Option<&String>,
+    dry_run: bool,
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, None)?;
+    let target_roots = get_roots(&config, root)?;
+    let clone_options =
+        CloneOptions::new(&config, CloneConfig::default(), false)?;
+
+    let foreign_repos = match from {
+        ImportSource::Manifest => {
+            let Some(manifest) = source else {
+                return Err(SrcError::Usage(
+                    "a manifest is required to import from a manifest"
+                        .to_string(),
+                )
+                .into());
+            };
+
+            return import_repos(
+                &config,
+                &roots,
+                &target_roots,
+                &clone_options,
+                &read_manifest(manifest)?,
+                dry_run,
+            );
+        }
+
+        ImportSource::Ghq => {
+            read_ghq(&source.cloned().map_or_else(get_ghq_root, Ok)?)
+        }
+
+        ImportSource::Mr => read_mrconfig(
+            &source.cloned().map_or_else(get_mrconfig_path, Ok)?,
+        )?,
+
+        ImportSource::Gita => {
+            read_gita(&source.cloned().map_or_else(get_gita_path, Ok)?)?
+        }
+    };
+
+    import_foreign_repos(
+        &config,
+        &roots,
+        &target_roots,
+        &clone_options,
+        &foreign_repos,
+        dry_run,
+    )
+}
@@ -2,15 +2,19 @@ use std::collections::HashSet;
 
 use anyhow::Result;
 use repo::{
-    config::{get_config, get_root_directory, get_username},
+    archive::list_archived_repos,
+    config::{get_config, get_username},
     list::{
         SortBy, get_repos, list_all_repos, list_managed_repos,
         list_unmanaged_repos, sort_case_insensitive,
     },
+    root::get_roots,
 };
 
 pub fn get_host_names(all: bool, hidden: bool) -> Result<Vec<String>> {
-    Ok(get_repos(&get_root_directory()?, all, hidden)?
+    let config = get_config()?;
+
+    Ok(get_repos(&config, &get_roots(&config, None)?, all, hidden)?
         .into_iter()
         .map(|repo| repo.host)
         .collect::<HashSet<_>>()
@@ -29,8 +33,11 @@ pub fn hosts(all: bool, hidden: bool) -> Result<()> {
 }
 
 pub fn get_owner_names(all: bool, hidden: bool) -> Result<Vec<String>> {
-    Ok(get_repos(&get_root_directory()?, all, hidden)?
+    let config = get_config()?;
+
+    Ok(get_repos(&config, &get_roots(&config, None)?, all, hidden)?
         .into_iter()
+        .filter(|repo| !repo.is_local())
         .map(|repo| repo.owner)
         .collect::<HashSet<_>>()
         .into_iter()
@@ -48,8 +55,9 @@ pub fn owners(all: bool, hidden: bool) -> Result<()> {
 }
 
 pub fn names(all: bool, hidden: bool, me: bool) -> Result<()> {
+    let config = get_config()?;
     let mut names: Vec<String> =
-        get_repos(&get_root_directory()?, all, hidden)?
+        get_repos(&config, &get_roots(&config, None)?, all, hidden)?
             .into_iter()
             .filter_map(|repo| {
                 if me {
@@ -93,26 +101,38 @@ const fn get_sort_by_value(sort_by: Option<&SortByOption>) -> Option<SortBy> {
 }
 
 pub fn list(
+    root: Option<&String>,
     host: Option<&String>,
     owner: Option<&String>,
     name: Option<&String>,
+    tag: Option<&String>,
     me: bool,
     no_host: bool,
     no_owner: bool,
+    aliases: bool,
     path: bool,
+    worktrees: bool,
+    submodules: bool,
     sort_by: Option<&SortByOption>,
 ) -> Result<()> {
+    let config = get_config()?;
+
     print!(
         "{}",
         list_managed_repos(
-            &get_config()?,
+            &config,
+            &get_roots(&config, root)?,
             host,
             owner,
             name,
+            tag,
             me,
             no_host,
             no_owner,
+            aliases,
             path,
+            worktrees,
+            submodules,
             get_sort_by_value(sort_by).as_ref()
         )?
         .join("\n")
@@ -121,23 +141,47 @@ pub fn list(
     Ok(())
 }
 
+pub fn list_archived(
+    host: Option<&String>,
+    owner: Option<&String>,
+    name: Option<&String>,
+    tag: Option<&String>,
+    me: bool,
+    no_host: bool,
+    no_owner: bool,
+    path: bool,
+) -> Result<()> {
+    let config = get_config()?;
+    let repos = list_archived_repos(
+        &config, host, owner, name, tag, me, no_host, no_owner, path,
+    )?;
+
+    print!("{}", repos.join("\n"));
+
+    Ok(())
+}
+
 pub fn list_unmanaged(
     hidden: bool,
     host: Option<&String>,
     owner: Option<&String>,
     name: Option<&String>,
+    tag: Option<&String>,
     me: bool,
     no_host: bool,
     no_owner: bool,
     path: bool,
     sort_by: Option<&SortByOption>,
 ) -> Result<()> {
+    let config = get_config()?;
     let repos = list_unmanaged_repos(
-        &get_config()?,
+        &config,
+        &get_roots(&config, None)?,
         hidden,
         host,
         owner,
         name,
+        tag,
         me,
         no_host,
         no_owner,
@@ -155,18 +199,22 @@ pub fn list_all(
     host: Option<&String>,
     owner: Option<&String>,
     name: Option<&String>,
+    tag: Option<&String>,
     me: bool,
     no_host: bool,
     no_owner: bool,
     path: bool,
     sort_by: Option<&SortByOption>,
 ) -> Result<()> {
+    let config = get_config()?;
     let repos = list_all_repos(
-        &get_config()?,
+        &config,
+        &get_roots(&config, None)?,
         hidden,
         host,
         owner,
         name,
+        tag,
         me,
         no_host,
         no_owner,
@@ -0,0 +1,72 @@
+use std::fs;
+
+use anyhow::Result;
+use repo::config::get_config;
+use repo::list::get_all_managed_repo_paths;
+use repo::mirror::{
+    get_mirror_directory, get_mirror_paths, get_unused_mirror_paths,
+};
+use repo::root::get_roots;
+
+use crate::error::Failures;
+use crate::git::git;
+use crate::log::{LogLevel, log};
+
+/// Fetch every mirror from its remote.
+pub fn update_mirrors() -> Result<()> {
+    let config = get_config()?;
+    let mut failures = Failures::default();
+
+    for path in get_mirror_paths(&get_mirror_directory(&config)?) {
+        let path = path.to_string_lossy();
+
+        failures.record(
+            git(&["-C", &path, "remote", "update", "--prune"]).map(|()| {
+                log(&LogLevel::Info, &format!("Updated {path}"));
+            }),
+        );
+    }
+
+    failures.finish()
+}
+
+/// Repack every mirror, and with `unused`, delete the mirrors that no managed
+/// repository borrows objects from.
+///
+/// Unreachable objects are never pruned, since clones made through a mirror
+/// may still need them.
+pub fn gc_mirrors(unused: bool) -> Result<()> {
+    let config = get_config()?;
+    let mut mirror_paths = get_mirror_paths(&get_mirror_directory(&config)?);
+    let mut failures = Failures::default();
+
+    if unused {
+        let repo_paths =
+            get_all_managed_repo_paths(&get_roots(&config, None)?);
+
+        for path in get_unused_mirror_paths(&mirror_paths, &repo_paths) {
+            failures.record(
+                fs::remove_dir_all(&path).map_err(Into::into).map(|()| {
+                    log(
+                        &LogLevel::Info,
+                        &format!("Removed unused mirror {}", path.display()),
+                    );
+                }),
+            );
+
+            mirror_paths.retain(|mirror_path| mirror_path != &path);
+        }
+    }
+
+    for path in mirror_paths {
+        failures.record(git(&[
+            "-C",
+            &path.to_string_lossy(),
+            "gc",
+            "--quiet",
+            "--prune=never",
+        ]));
+    }
+
+    failures.finish()
+}
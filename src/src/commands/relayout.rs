@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use repo::config::{Config, get_config};
+use repo::list::get_managed_repo_paths;
+use repo::repo::Repo;
+use repo::root::{Root, get_roots};
+use repo::worktree::{get_worktrees, get_worktrees_path};
+
+use crate::error::Failures;
+use crate::git::git;
+use crate::log::{LogLevel, log};
+
+/// Remove the directories left empty between `path` and `root`.
+pub fn remove_empty_parents(path: &Path, root: &Path) {
+    for ancestor in path.ancestors().skip(1) {
+        if ancestor == root || fs::remove_dir(ancestor).is_err() {
+            break;
+        }
+    }
+}
+
+/// Move the repository at `path` to `target`, along with its worktrees
+/// directory, and remove the directories left empty up to `root`.
+///
+/// # Errors
+///
+/// Will return an error if `target` already exists, or moving the repository
+/// or repairing its worktrees fails
+pub fn move_repo(path: &Path, target: &Path, root: &Path) -> Result<()> {
+    if target.exists() {
+        bail!(
+            "can't move {} to {}: it already exists",
+            path.display(),
+            target.display()
+        );
+    }
+
+    let worktrees_path = get_worktrees_path(path);
+    let target_worktrees_path = get_worktrees_path(target);
+
+    let worktrees: Vec<PathBuf> = get_worktrees(path)?
+        .into_iter()
+        .map(|worktree| {
+            worktree.path.strip_prefix(&worktrees_path).map_or_else(
+                |_| worktree.path.clone(),
+                |relative_path| target_worktrees_path.join(relative_path),
+            )
+        })
+        .collect();
+
+    // A layout can place a repository inside the directory it is in now, so
+    // move it out of the way first.
+    let mut source = path.to_path_buf();
+
+    if target.starts_with(path) {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+
+        name.push(".relayout");
+        source = path.with_file_name(name);
+
+        fs::rename(path, &source)?;
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::rename(&source, target)?;
+
+    if worktrees_path.exists() {
+        fs::rename(&worktrees_path, &target_worktrees_path)?;
+    }
+
+    if !worktrees.is_empty() {
+        let target = target.to_string_lossy();
+        let worktrees: Vec<String> = worktrees
+            .iter()
+            .map(|worktree| worktree.to_string_lossy().to_string())
+            .collect();
+
+        let mut args = vec!["-C", &target, "worktree", "repair"];
+
+        args.extend(worktrees.iter().map(String::as_str));
+        git(&args)?;
+    }
+
+    remove_empty_parents(path, root);
+
+    Ok(())
+}
+
+/// Move the repository at `path` to where the layout of `root` places it.
+fn relayout_repo(
+    config: &Config,
+    root: &Root,
+    path: &Path,
+    dry_run: bool,
+) -> Result<bool> {
+    let repo = Repo::from(&path.to_string_lossy(), &config.remote_names)?;
+    let target = repo.managed_path(root);
+
+    if target == path {
+        return Ok(false);
+    }
+
+    if target.exists() {
+        bail!(
+            "can't move {repo} to {}: it already exists",
+            target.display()
+        );
+    }
+
+    let verb = if dry_run { "Would move" } else { "Moving" };
+
+    log(
+        &LogLevel::Info,
+        &format!("{verb} {} to {}", path.display(), target.display()),
+    );
+
+    if !dry_run {
+        move_repo(path, &target, Path::new(&root.path))?;
+    }
+
+    Ok(true)
+}
+
+/// Move managed repositories to where the configured layout places them,
+/// within the root they are in.
+pub fn relayout(root: Option<&String>, dry_run: bool) -> Result<()> {
+    let config = get_config()?;
+    let mut failures = Failures::default();
+    let mut moved = 0;
+
+    log(
+        &LogLevel::Debug,
+        &format!("moving repositories to the layout {:?}", config.layout),
+    );
+
+    for root in get_roots(&config, root)? {
+        for path in get_managed_repo_paths(&root.path) {
+            failures.record(
+                relayout_repo(&config, &root, Path::new(&path), dry_run)
+                    .map(|was_moved| moved += usize::from(was_moved)),
+            );
+        }
+    }
+
+    let verb = if dry_run { "Would move" } else { "Moved" };
+
+    log(&LogLevel::Info, &format!("{verb} {moved} repositories."));
+
+    failures.finish()
+}
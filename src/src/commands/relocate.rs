@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use repo::config::{Config, get_config};
+use repo::list::get_managed_repo_paths;
+use repo::metadata::{
+    Metadata, read_metadata, record_relocation, write_metadata,
+};
+use repo::repo::{Repo, get_local_source_path};
+use repo::root::{Root, get_roots, root_for_managed};
+
+use crate::commands::relayout::move_repo;
+use crate::error::Failures;
+use crate::log::{LogLevel, log};
+use crate::repo::find_managed_repo_path;
+
+/// The paths of the managed repositories named by `repos`, by path or by the
+/// identity they are kept under, or of all of them if none are given.
+fn select_paths(
+    config: &Config,
+    roots: &[Root],
+    repos: &[String],
+    failures: &mut Failures,
+) -> Vec<PathBuf> {
+    if repos.is_empty() {
+        return roots
+            .iter()
+            .flat_map(|root| get_managed_repo_paths(&root.path))
+            .map(PathBuf::from)
+            .collect();
+    }
+
+    let mut paths = vec![];
+
+    for repo in repos {
+        let path = get_local_source_path(repo).map_or_else(
+            || {
+                find_managed_repo_path(config, roots, repo)
+                    .map(|(_, path)| path)
+            },
+            Ok,
+        );
+
+        match path {
+            Ok(path) if !paths.contains(&path) => paths.push(path),
+            Ok(_) => {}
+            Err(error) => failures.record(Err(error)),
+        }
+    }
+
+    paths
+}
+
+/// Move the repository at `path` to where its current remote places it.
+fn relocate_repo(
+    config: &Config,
+    roots: &[Root],
+    path: &Path,
+    metadata: &mut Metadata,
+    dry_run: bool,
+) -> Result<bool> {
+    let Some(root) = roots.iter().find(|root| path.starts_with(&root.path))
+    else {
+        bail!("{} is not in a managed root", path.display());
+    };
+
+    let repo = Repo::from(&path.to_string_lossy(), &config.remote_names)?;
+    let target = repo.managed_path(root_for_managed(roots, root, &repo));
+
+    if target == path {
+        return Ok(false);
+    }
+
+    if target.exists() {
+        match Repo::from(&target.to_string_lossy(), &config.remote_names) {
+            Ok(other) if other == repo => bail!(
+                "can't move {} to {}: {repo} is already cloned there",
+                path.display(),
+                target.display()
+            ),
+
+            Ok(other) => bail!(
+                "can't move {repo} to {}: {other} is there",
+                target.display()
+            ),
+
+            Err(_) => bail!(
+                "can't move {repo} to {}: it already exists",
+                target.display()
+            ),
+        }
+    }
+
+    let verb = if dry_run { "Would move" } else { "Moving" };
+
+    log(
+        &LogLevel::Info,
+        &format!("{verb} {} to {}", path.display(), target.display()),
+    );
+
+    if !dry_run {
+        move_repo(path, &target, Path::new(&root.path))?;
+        record_relocation(&repo, path, &target)?;
+
+        metadata.relocate(root, path, &repo);
+        write_metadata(metadata)?;
+    }
+
+    Ok(true)
+}
+
+/// Move managed repositories whose remote has changed, for example after
+/// being renamed or transferred, to where their current remote places them.
+pub fn relocate(
+    repos: &[String],
+    root: Option<&String>,
+    dry_run: bool,
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, None)?;
+    let selected_roots = get_roots(&config, root)?;
+    let mut metadata = read_metadata()?;
+    let mut failures = Failures::default();
+    let mut moved = 0;
+
+    for path in select_paths(&config, &selected_roots, repos, &mut failures) {
+        failures.record(
+            relocate_repo(&config, &roots, &path, &mut metadata, dry_run)
+                .map(|was_moved| moved += usize::from(was_moved)),
+        );
+    }
+
+    let verb = if dry_run { "Would move" } else { "Moved" };
+
+    log(&LogLevel::Info, &format!("{verb} {moved} repositories."));
+
+    failures.finish()
+}
@@ -1,88 +1,121 @@
 use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use colored::Colorize;
 use inquire::{Confirm, MultiSelect};
-use repo::config::{get_config, get_root_directory};
+use repo::config::get_config;
+use repo::list::get_repos;
+use repo::repo::Repo;
+use repo::root::{find_managed_path, get_roots};
+use repo::tag::filter_by_tag;
 
+use crate::error::{Failures, SrcError};
+use crate::log::{LogLevel, log};
 use crate::repo::parse_repos_with_error_log;
 
 pub fn remove_repo(managed_path: &str) -> Result<()> {
-    Command::new("rm")
+    if !Command::new("rm")
         .args(vec!["--force", "--recursive", managed_path])
-        .status()?;
+        .status()?
+        .success()
+    {
+        bail!("failed to remove {managed_path}");
+    }
 
     Ok(())
 }
 
 pub fn remove(
     repos: &[String],
+    root: Option<&String>,
     host: Option<&String>,
     owner: Option<&String>,
     me: bool,
+    tag: Option<&String>,
     force: bool,
 ) -> Result<()> {
     let config = get_config()?;
     let owner = if me { config.owner.as_ref() } else { owner };
-    let repos = parse_repos_with_error_log(&config, repos, host, owner, true)?;
-    let root_directory = &get_root_directory()?;
+    let roots = get_roots(&config, root)?;
+    let mut failures = Failures::default();
+
+    let repos = if repos.is_empty() && tag.is_some() {
+        let mut repos = get_repos(&config, &roots, false, false)?;
+
+        // `--me` without a configured owner matches nothing, as in `list`.
+        repos.retain(|repo| {
+            host.is_none_or(|host| &repo.host == host)
+                && owner.is_none_or(|owner| &repo.owner == owner)
+                && !(me && owner.is_none())
+        });
+
+        repos
+    } else {
+        parse_repos_with_error_log(
+            &config,
+            &roots,
+            repos,
+            host,
+            owner,
+            true,
+            Some(&mut failures),
+        )?
+    };
+
+    let repos = filter_by_tag(&config, repos, tag)?;
 
-    if repos.is_empty() {
-        return Ok(());
+    let managed_paths: Vec<(Repo, String)> = repos
+        .into_iter()
+        .filter_map(|repo| {
+            let path = find_managed_path(&roots, &repo)?;
+
+            Some((repo, path.to_string_lossy().to_string()))
+        })
+        .collect();
+
+    if managed_paths.is_empty() {
+        return failures.finish();
     }
 
     let mut message = "Are you sure you want to proceed?".to_string();
 
-    let repos = if repos.len() > 1 {
-        let selected_repos = MultiSelect::new(
+    let managed_paths = if managed_paths.len() > 1 {
+        let selected_paths = MultiSelect::new(
             "Select repositories to remove",
-            repos
+            managed_paths
                 .iter()
-                .map(|repo| repo.managed_path_name(root_directory))
+                .map(|(_, path)| path.clone())
                 .collect::<Vec<String>>(),
         )
-        .prompt();
-
-        if let Ok(selected_repos) = selected_repos {
-            repos
-                .into_iter()
-                .filter(|repo| {
-                    selected_repos
-                        .contains(&repo.managed_path_name(root_directory))
-                })
-                .collect()
-        } else {
-            // TODO: exit with error?
-            return Ok(());
-        }
-    } else if let Some(repo) = repos.first() {
-        message = format!(
-            "Are you sure you want to remove {}?",
-            repo.managed_path_name(root_directory).cyan()
-        );
+        .prompt()?;
 
-        repos
+        managed_paths
+            .into_iter()
+            .filter(|(_, path)| selected_paths.contains(path))
+            .collect()
+    } else if let Some((_, path)) = managed_paths.first() {
+        message = format!("Are you sure you want to remove {}?", path.cyan());
+
+        managed_paths
     } else {
-        // TODO: exit with error?
-        return Ok(());
+        return failures.finish();
     };
 
     let remove = force
-        || matches!(
-            Confirm::new(&message)
-                .with_default(false)
-                .with_help_message("This cannot be undone.")
-                .prompt(),
-            Ok(true)
-        );
-
-    if remove {
-        for repo in repos {
-            remove_repo(&repo.managed_path_name(root_directory))?;
-
-            println!("Removed {repo}.");
-        }
+        || Confirm::new(&message)
+            .with_default(false)
+            .with_help_message("This cannot be undone.")
+            .prompt()?;
+
+    if !remove {
+        return Err(SrcError::Cancelled.into());
     }
 
-    Ok(())
+    for (repo, path) in managed_paths {
+        failures.record(remove_repo(&path).map(|()| {
+            log(&LogLevel::Info, &format!("Removed {repo}."));
+        }));
+    }
+
+    failures.finish()
 }
@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use repo::config::{Config, get_config};
+use repo::list::get_managed_repo_paths;
+use repo::metadata::{
+    Metadata, read_metadata, record_relocation, write_metadata,
+};
+use repo::rename::{Rename, rename_remote};
+use repo::repo::Repo;
+use repo::root::{Root, get_roots, root_for_managed};
+
+use crate::commands::relayout::move_repo;
+use crate::error::Failures;
+use crate::log::{LogLevel, log};
+
+/// Point the remote that identifies the repository at `path` to where
+/// `rename` moves it, and move it to where its new name places it. Returns
+/// whether anything changed.
+fn rename_repo(
+    config: &Config,
+    roots: &[Root],
+    path: &Path,
+    rename: &Rename,
+    metadata: &mut Metadata,
+    dry_run: bool,
+) -> Result<bool> {
+    let Some(root) = roots.iter().find(|root| path.starts_with(&root.path))
+    else {
+        bail!("{} is not in a managed root", path.display());
+    };
+
+    let repo = Repo::from(&path.to_string_lossy(), &config.remote_names)?;
+    let Some(renamed) = rename.rename_repo(&repo) else {
+        return Ok(false);
+    };
+
+    let target =
+        Some(renamed.managed_path(root_for_managed(roots, root, &renamed)))
+            .filter(|target| target != path);
+
+    // Check before touching the remote, so that nothing is left half done.
+    if let Some(target) = &target
+        && target.exists()
+    {
+        bail!(
+            "can't move {repo} to {}: it already exists (is {renamed} \
+             already cloned?)",
+            target.display()
+        );
+    }
+
+    let remote = rename_remote(path, &config.remote_names, rename, dry_run)?;
+
+    if let Some((name, url)) = &remote {
+        let verb = if dry_run { "Would point" } else { "Pointing" };
+
+        log(
+            &LogLevel::Info,
+            &format!("{verb} the {name} remote of {repo} to {url}"),
+        );
+    }
+
+    let Some(target) = target else {
+        return Ok(remote.is_some());
+    };
+
+    let verb = if dry_run { "Would move" } else { "Moving" };
+
+    log(
+        &LogLevel::Info,
+        &format!("{verb} {} to {}", path.display(), target.display()),
+    );
+
+    if !dry_run {
+        move_repo(path, &target, Path::new(&root.path))?;
+        record_relocation(&renamed, path, &target)?;
+
+        metadata.relocate(root, path, &renamed);
+        write_metadata(metadata)?;
+    }
+
+    Ok(true)
+}
+
+/// Apply `rename` to every managed repository it affects.
+fn rename(
+    rename: &Rename,
+    root: Option<&String>,
+    dry_run: bool,
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, None)?;
+    let mut metadata = read_metadata()?;
+    let mut failures = Failures::default();
+    let mut renamed = 0;
+
+    for selected_root in get_roots(&config, root)? {
+        for path in get_managed_repo_paths(&selected_root.path) {
+            failures.record(
+                rename_repo(
+                    &config,
+                    &roots,
+                    Path::new(&path),
+                    rename,
+                    &mut metadata,
+                    dry_run,
+                )
+                .map(|was_renamed| renamed += usize::from(was_renamed)),
+            );
+        }
+    }
+
+    let verb = if dry_run { "Would update" } else { "Updated" };
+
+    log(&LogLevel::Info, &format!("{verb} {renamed} repositories."));
+
+    failures.finish()
+}
+
+/// Rewrite the remotes of repositories owned by `from`, or by namespaces
+/// nested in it, to be owned by `to`, and move them to match.
+pub fn rename_owner(
+    from: &str,
+    to: &str,
+    host: Option<&String>,
+    root: Option<&String>,
+    dry_run: bool,
+) -> Result<()> {
+    rename(
+        &Rename::Owner {
+            from: from.to_string(),
+            to: to.to_string(),
+            host: host.cloned(),
+        },
+        root,
+        dry_run,
+    )
+}
+
+/// Rewrite the remotes of repositories hosted at `from` to be hosted at
+/// `to`, and move them to match.
+pub fn move_host(
+    from: &str,
+    to: &str,
+    root: Option<&String>,
+    dry_run: bool,
+) -> Result<()> {
+    rename(
+        &Rename::Host {
+            from: from.to_string(),
+            to: to.to_string(),
+        },
+        root,
+        dry_run,
+    )
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use anyhow::Result;
+use repo::config::get_config;
+use repo::repo::Repo;
+use repo::root::get_roots;
+use repo::status::get_repo_status;
+use repo::submodule::{Submodule, get_submodules};
+
+use crate::error::Failures;
+use crate::repo::select_managed_repos;
+
+fn describe_submodule(submodule: &Submodule) -> String {
+    let mut description = submodule.path.display().to_string();
+
+    if !submodule.initialized {
+        description.push_str(" not initialized");
+    }
+
+    if submodule.moved {
+        description.push_str(" moved");
+    }
+
+    if submodule.dirty {
+        description.push_str(" dirty");
+    }
+
+    description
+}
+
+/// The status line of the repository at `path`, followed by one for each of
+/// its submodules.
+fn describe_repo(
+    repo: &Repo,
+    path: &Path,
+    remote_names: &[String],
+) -> Result<Vec<String>> {
+    let mut lines = vec![format!(
+        "{repo} {}",
+        get_repo_status(path, remote_names)?.describe()
+    )];
+
+    lines.extend(
+        get_submodules(path)?
+            .iter()
+            .map(|submodule| format!("  {}", describe_submodule(submodule))),
+    );
+
+    Ok(lines)
+}
+
+/// Show the branch of each repository, how far it is from its upstream and
+/// whether it has uncommitted changes, with its submodules under it.
+pub fn status(
+    repos: &[String],
+    root: Option<&String>,
+    tag: Option<&String>,
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, root)?;
+    let mut failures = Failures::default();
+    let mut lines = vec![];
+
+    for (repo, path) in
+        select_managed_repos(&config, &roots, repos, tag, &mut failures)?
+    {
+        failures.record(
+            describe_repo(&repo, &path, &config.remote_names)
+                .map(|repo_lines| lines.extend(repo_lines)),
+        );
+    }
+
+    println!("{}", lines.join("\n"));
+
+    failures.finish()
+}
@@ -0,0 +1,119 @@
+use std::path::Path;
+
+use anyhow::Result;
+use repo::config::get_config;
+use repo::fork::{UPSTREAM_REMOTE, get_fork_status, has_upstream_remote};
+use repo::repo::{Repo, get_remote_name};
+use repo::root::get_roots;
+use repo::status::get_repo_status;
+
+use crate::error::Failures;
+use crate::git::git;
+use crate::log::{LogLevel, log};
+use crate::repo::select_managed_repos;
+
+/// Fetch the `upstream` remote of a fork, and fast-forward the local copy of
+/// its default branch.
+fn sync_fork(path: &Path, remote_names: &[String]) -> Result<()> {
+    let path_name = path.to_string_lossy();
+
+    git(&["-C", &path_name, "fetch", "--prune", UPSTREAM_REMOTE])?;
+
+    let Some(fork_status) = get_fork_status(path, remote_names)? else {
+        return Ok(());
+    };
+
+    if fork_status.behind.is_none_or(|behind| behind == 0) {
+        return Ok(());
+    }
+
+    let branch = &fork_status.branch;
+    let upstream_branch = format!("refs/remotes/{UPSTREAM_REMOTE}/{branch}");
+
+    if get_repo_status(path, remote_names)?.branch.as_ref() == Some(branch) {
+        git(&["-C", &path_name, "merge", "--ff-only", &upstream_branch])
+    } else {
+        // Fetching from the repository itself updates the branch without
+        // checking it out, and refuses anything but a fast-forward.
+        git(&[
+            "-C",
+            &path_name,
+            "fetch",
+            ".",
+            &format!("{upstream_branch}:refs/heads/{branch}"),
+        ])
+    }
+}
+
+/// Fetch the remote chosen from `remote_names`, fast-forward the current
+/// branch to its upstream, bring a fork's default branch up to date with the
+/// repository it was forked from, and, with `submodules`, check out the
+/// submodules it records. Repositories without remotes are left alone.
+fn sync_repo(
+    repo: &Repo,
+    path: &Path,
+    remote_names: &[String],
+    submodules: bool,
+) -> Result<()> {
+    let Some(remote) = get_remote_name(path, remote_names)? else {
+        log(
+            &LogLevel::Debug,
+            &format!("{repo} has no remote to sync with"),
+        );
+
+        return Ok(());
+    };
+
+    let path_name = path.to_string_lossy();
+
+    git(&["-C", &path_name, "fetch", "--prune", &remote])?;
+
+    if get_repo_status(path, remote_names)?
+        .behind
+        .is_some_and(|behind| behind > 0)
+    {
+        git(&["-C", &path_name, "merge", "--ff-only", "@{upstream}"])?;
+    }
+
+    if has_upstream_remote(path) {
+        sync_fork(path, remote_names)?;
+    }
+
+    if submodules && path.join(".gitmodules").exists() {
+        git(&[
+            "-C",
+            &path_name,
+            "submodule",
+            "update",
+            "--init",
+            "--recursive",
+        ])?;
+    }
+
+    log(&LogLevel::Info, &format!("Synced {repo}"));
+
+    Ok(())
+}
+
+pub fn sync(
+    repos: &[String],
+    root: Option<&String>,
+    tag: Option<&String>,
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, root)?;
+    let mut failures = Failures::default();
+
+    for (repo, path) in
+        select_managed_repos(&config, &roots, repos, tag, &mut failures)?
+    {
+        failures.record(sync_repo(
+            &repo,
+            &path,
+            &config.remote_names,
+            config.submodules,
+        ));
+    }
+
+    failures.finish()
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use repo::config::get_config;
+use repo::metadata::{read_metadata, write_metadata};
+use repo::root::get_roots;
+use repo::tag::{get_tags, matches_pattern};
+
+use crate::log::{LogLevel, log};
+use crate::repo::find_managed_repo;
+
+pub fn add_tags(repo: &str, tags: &[String]) -> Result<()> {
+    let config = get_config()?;
+    let repo = find_managed_repo(&config, &get_roots(&config, None)?, repo)?;
+    let mut metadata = read_metadata()?;
+
+    metadata.get_mut(&repo).tags.extend(tags.iter().cloned());
+
+    write_metadata(&mut metadata)?;
+
+    Ok(())
+}
+
+pub fn remove_tags(repo: &str, tags: &[String]) -> Result<()> {
+    let config = get_config()?;
+    let repo = find_managed_repo(&config, &get_roots(&config, None)?, repo)?;
+    let mut metadata = read_metadata()?;
+    let repo_metadata = metadata.get_mut(&repo);
+
+    for tag in tags {
+        if !repo_metadata.tags.remove(tag)
+            && config.tags.get(tag).is_some_and(|patterns| {
+                patterns
+                    .iter()
+                    .any(|pattern| matches_pattern(&repo, pattern))
+            })
+        {
+            log(
+                &LogLevel::Warning,
+                &format!(
+                    "{repo} is tagged {tag:?} by the config, which can't be \
+                     changed with `src tag remove`"
+                ),
+            );
+        }
+    }
+
+    write_metadata(&mut metadata)?;
+
+    Ok(())
+}
+
+/// List the tags of `repo`, or every tag in use if `repo` is `None`.
+pub fn list_tags(repo: Option<&String>) -> Result<()> {
+    let config = get_config()?;
+    let metadata = read_metadata()?;
+
+    let tags: BTreeSet<String> = if let Some(repo) = repo {
+        let repo =
+            find_managed_repo(&config, &get_roots(&config, None)?, repo)?;
+
+        get_tags(&config, &metadata, &repo)
+    } else {
+        metadata
+            .repos
+            .values()
+            .flat_map(|repo_metadata| repo_metadata.tags.iter().cloned())
+            .chain(config.tags.keys().cloned())
+            .collect()
+    };
+
+    println!("{}", tags.into_iter().collect::<Vec<String>>().join("\n"));
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anyhow::{Result, anyhow};
+use repo::clone::get_clone_state;
+use repo::config::get_config;
+use repo::repo::get_remote_name;
+use repo::root::get_roots;
+
+use crate::git::git;
+use crate::log::{LogLevel, log};
+use crate::repo::find_managed_repo_path;
+
+/// Turn a shallow, single-branch or partial clone into a full clone.
+pub fn unshallow(repo: &str, root: Option<&String>) -> Result<()> {
+    let config = get_config()?;
+    let (repo, path) =
+        find_managed_repo_path(&config, &get_roots(&config, root)?, repo)?;
+    let remote = get_remote_name(&path, &config.remote_names)?
+        .ok_or_else(|| anyhow!("{repo} has no remote to fetch from"))?;
+    let state = get_clone_state(&path, &remote)?;
+
+    if state.is_full() {
+        log(&LogLevel::Info, &format!("{repo} is already a full clone"));
+
+        return Ok(());
+    }
+
+    let path = path.to_string_lossy();
+    let promisor = format!("remote.{remote}.promisor");
+    let filter = format!("remote.{remote}.partialclonefilter");
+    let fetch = format!("remote.{remote}.fetch");
+    let refspec = format!("+refs/heads/*:refs/remotes/{remote}/*");
+
+    // Fetch as a full clone would first, and only change the config once that
+    // has worked, so that a failed fetch leaves the clone as it was.
+    let promisor_override = format!("{promisor}=false");
+    let filter_override = format!("{filter}=");
+    let mut args = vec!["-C", &path];
+
+    if state.partial {
+        args.extend(["-c", &promisor_override, "-c", &filter_override]);
+    }
+
+    args.push("fetch");
+
+    if state.shallow {
+        args.push("--unshallow");
+    }
+
+    // Fetch every object again, since the ones left out by the filter can't
+    // be told apart from the ones already fetched.
+    if state.partial {
+        args.push("--refetch");
+    }
+
+    args.push(&remote);
+
+    if state.single_branch {
+        args.push(&refspec);
+    }
+
+    git(&args)?;
+
+    if state.single_branch {
+        git(&["-C", &path, "config", "--replace-all", &fetch, &refspec])?;
+    }
+
+    if state.partial {
+        git(&["-C", &path, "config", &promisor, "false"])?;
+        git(&["-C", &path, "config", "--unset", &filter])?;
+    }
+
+    log(
+        &LogLevel::Info,
+        &format!("Converted {repo} to a full clone"),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::fs;
+
+use anyhow::Result;
+use repo::config::get_config;
+use repo::list::get_repos;
+use repo::root::{find_managed_path, get_roots};
+use repo::worktree::{get_worktree_path, get_worktrees, get_worktrees_path};
+
+use crate::error::SrcError;
+use crate::git::git;
+use crate::log::{LogLevel, log};
+use crate::repo::find_managed_repo_path;
+
+pub fn add_worktree(
+    repo: &str,
+    branch: &str,
+    new_branch: bool,
+    root: Option<&String>,
+) -> Result<()> {
+    let config = get_config()?;
+    let (repo, path) =
+        find_managed_repo_path(&config, &get_roots(&config, root)?, repo)?;
+    let worktree_path = get_worktree_path(&path, branch);
+
+    if worktree_path.exists() {
+        return Err(SrcError::Usage(format!(
+            "{} already exists",
+            worktree_path.display()
+        ))
+        .into());
+    }
+
+    let path = path.to_string_lossy();
+    let worktree_path = worktree_path.to_string_lossy();
+
+    if new_branch {
+        git(&["-C", &path, "worktree", "add", "-b", branch, &worktree_path])?;
+    } else {
+        git(&["-C", &path, "worktree", "add", &worktree_path, branch])?;
+    }
+
+    log(
+        &LogLevel::Info,
+        &format!("Added worktree for {repo} [{branch}] at {worktree_path}"),
+    );
+
+    Ok(())
+}
+
+pub fn list_worktrees(
+    repo: Option<&String>,
+    root: Option<&String>,
+) -> Result<()> {
+    let config = get_config()?;
+    let roots = get_roots(&config, root)?;
+
+    let repos = match repo {
+        Some(repo) => vec![find_managed_repo_path(&config, &roots, repo)?],
+
+        None => get_repos(&config, &roots, false, false)?
+            .into_iter()
+            .filter_map(|repo| {
+                let path = find_managed_path(&roots, &repo)?;
+
+                Some((repo, path))
+            })
+            .collect(),
+    };
+
+    let mut lines = vec![];
+
+    for (repo, path) in repos {
+        for worktree in get_worktrees(&path)? {
+            lines.push(format!(
+                "{repo} [{}] {}",
+                worktree.branch.as_deref().unwrap_or("detached"),
+                worktree.path.display()
+            ));
+        }
+    }
+
+    lines.sort();
+
+    println!("{}", lines.join("\n"));
+
+    Ok(())
+}
+
+pub fn remove_worktree(
+    repo: &str,
+    branch: &str,
+    force: bool,
+    root: Option<&String>,
+) -> Result<()> {
+    let config = get_config()?;
+    let (repo, path) =
+        find_managed_repo_path(&config, &get_roots(&config, root)?, repo)?;
+    let worktrees_path = get_worktrees_path(&path);
+    let worktree_path = get_worktree_path(&path, branch);
+    let path = path.to_string_lossy();
+    let worktree_path_name = worktree_path.to_string_lossy();
+
+    let mut args = vec!["-C", &path, "worktree", "remove"];
+
+    if force {
+        args.push("--force");
+    }
+
+    args.push(&worktree_path_name);
+
+    git(&args)?;
+
+    // Clean up the directories left empty by branch names like `feature/x`,
+    // up to and including `<repo>.worktrees`.
+    for directory in worktree_path.ancestors().skip(1) {
+        if !directory.starts_with(&worktrees_path)
+            || fs::remove_dir(directory).is_err()
+        {
+            break;
+        }
+    }
+
+    log(
+        &LogLevel::Info,
+        &format!("Removed worktree for {repo} [{branch}]"),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use inquire::InquireError;
+use repo::error::SrcRepoError;
+use thiserror::Error;
+
+use crate::log::{LogLevel, log};
+
+/// Exit codes returned by `src`.
+///
+/// These are part of the command line interface and should not be
+/// renumbered.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// The command completed successfully
+    Success = 0,
+
+    /// An error that doesn't fall into any of the other categories
+    Failure = 1,
+
+    /// Invalid arguments or input (also used by `clap` for parse errors)
+    Usage = 2,
+
+    /// The configuration could not be loaded or is incomplete
+    Config = 3,
+
+    /// Some, but not all, of the selected repositories failed
+    PartialFailure = 4,
+
+    /// A git command or git operation failed
+    Git = 5,
+
+    /// The user cancelled an interactive prompt
+    Cancelled = 130,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        Self::from(status as u8)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SrcError {
+    #[error("{failed} of {total} repositories failed")]
+    Batch {
+        failed: usize,
+        total: usize,
+        status: Status,
+    },
+
+    #[error("cancelled")]
+    Cancelled,
+
+    #[error("`{0}` failed")]
+    Git(String),
+
+    #[error("{0}")]
+    Usage(String),
+}
+
+/// Map an error to the exit status documented for it.
+pub fn exit_status(error: &anyhow::Error) -> Status {
+    if let Some(error) = error.downcast_ref::<SrcError>() {
+        return match error {
+            SrcError::Batch {
+                failed,
+                total,
+                status,
+            } => {
+                if failed < total {
+                    Status::PartialFailure
+                } else {
+                    *status
+                }
+            }
+
+            SrcError::Cancelled => Status::Cancelled,
+            SrcError::Git(_) => Status::Git,
+            SrcError::Usage(_) => Status::Usage,
+        };
+    }
+
+    if let Some(error) = error.downcast_ref::<SrcRepoError>() {
+        return match error {
+            SrcRepoError::Config
+            | SrcRepoError::HomeDir
+            | SrcRepoError::Layout(_)
+            | SrcRepoError::Profile(_)
+            | SrcRepoError::UnknownForge(_) => Status::Config,
+
+            SrcRepoError::Git(_)
+            | SrcRepoError::GitUrl
+            | SrcRepoError::Remote(_) => Status::Git,
+
+            SrcRepoError::GitUrlParseError(_)
+            | SrcRepoError::RepoPath
+            | SrcRepoError::Root(_) => Status::Usage,
+
+            SrcRepoError::Archive(_)
+            | SrcRepoError::Backup(_)
+            | SrcRepoError::Filter
+            | SrcRepoError::Forge(_)
+            | SrcRepoError::Import
+            | SrcRepoError::Metadata => Status::Failure,
+        };
+    }
+
+    if let Some(
+        InquireError::OperationCanceled | InquireError::OperationInterrupted,
+    ) = error.downcast_ref::<InquireError>()
+    {
+        return Status::Cancelled;
+    }
+
+    Status::Failure
+}
+
+/// Collects the results of an operation run on several repositories, so that
+/// one failure doesn't stop the rest.
+#[derive(Default)]
+pub struct Failures {
+    failed: usize,
+    total: usize,
+    status: Option<Status>,
+}
+
+impl Failures {
+    pub fn record(&mut self, result: Result<()>) {
+        self.total += 1;
+
+        if let Err(error) = result {
+            let status = exit_status(&error);
+
+            log(&LogLevel::Error, &error.to_string());
+
+            self.failed += 1;
+
+            self.status = match self.status {
+                Some(previous) if previous != status => Some(Status::Failure),
+                _ => Some(status),
+            };
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `SrcError::Batch` if any recorded result was an error
+    pub fn finish(self) -> Result<()> {
+        self.status.map_or(Ok(()), |status| {
+            Err(SrcError::Batch {
+                failed: self.failed,
+                total: self.total,
+                status,
+            }
+            .into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_partial_failure_when_some_repos_fail() {
+        let mut failures = Failures::default();
+
+        failures.record(Ok(()));
+        failures.record(Err(SrcError::Git("git clone".to_string()).into()));
+
+        let error = failures.finish().unwrap_err();
+
+        assert_eq!(exit_status(&error), Status::PartialFailure);
+    }
+
+    #[test]
+    fn it_reports_the_underlying_status_when_every_repo_fails() {
+        let mut failures = Failures::default();
+
+        failures.record(Err(SrcError::Git("git clone".to_string()).into()));
+
+        let error = failures.finish().unwrap_err();
+
+        assert_eq!(exit_status(&error), Status::Git);
+    }
+}
@@ -0,0 +1,24 @@
+use std::process::Command;
+
+use anyhow::Result;
+
+use crate::error::SrcError;
+use crate::log::{LogLevel, log};
+
+/// Run `git` with `args`, returning an error if it exits unsuccessfully.
+///
+/// # Errors
+///
+/// Will return `SrcError::Git` if git can't be run or exits with a non-zero
+/// status
+pub fn git(args: &[&str]) -> Result<()> {
+    let command = format!("git {}", args.join(" "));
+
+    log(&LogLevel::Debug, &format!("running `{command}`"));
+
+    if Command::new("git").args(args).status()?.success() {
+        Ok(())
+    } else {
+        Err(SrcError::Git(command).into())
+    }
+}
@@ -1,22 +1,150 @@
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
 use colored::Colorize;
+use repo::log::set_logger;
+pub use repo::log::{LogLevel, log};
 
-pub enum LogLevel {
-    Error,
-    // Info,
-    // Warning,
+#[derive(clap::ValueEnum, Clone, Copy, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
 }
 
-pub fn log(level: &LogLevel, message: &str) {
-    let message = match level {
-        LogLevel::Error => format!("{}: {message}", "error".bold().red()),
-        // LogLevel::Warning => format!("warning: {message}"),
-        // LogLevel::Info => format!("info: {message}"),
-    };
+struct Settings {
+    verbosity: i8,
+    log_file: Option<Mutex<File>>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+const DEFAULT_VERBOSITY: i8 = LogLevel::Info as i8;
+
+/// Configure what gets logged and where, and route messages from the `repo`
+/// crate through `src`.
+///
+/// Each `verbose` raises the level by one (`Debug`, then `Trace`), and each
+/// `quiet` lowers it (`Warning`, then `Error`, then nothing). The log file, if
+/// any, receives every message regardless of verbosity.
+///
+/// # Errors
+///
+/// Will return an error if `log_file` can't be opened for appending
+pub fn init(
+    verbose: u8,
+    quiet: u8,
+    color: ColorChoice,
+    log_file: Option<&Path>,
+) -> Result<()> {
+    match color {
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+
+        ColorChoice::Auto => {
+            if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                colored::control::set_override(false);
+            }
+        }
+    }
+
+    let log_file = log_file
+        .map(|path| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map(Mutex::new)
+        })
+        .transpose()?;
 
+    let _ = SETTINGS.set(Settings {
+        verbosity: get_verbosity(verbose, quiet),
+        log_file,
+    });
+
+    set_logger(write);
+
+    Ok(())
+}
+
+fn get_verbosity(verbose: u8, quiet: u8) -> i8 {
+    DEFAULT_VERBOSITY
+        .saturating_add(i8::try_from(verbose).unwrap_or(i8::MAX))
+        .saturating_sub(i8::try_from(quiet).unwrap_or(i8::MAX))
+}
+
+const fn is_shown(level: LogLevel, verbosity: i8) -> bool {
+    level as i8 <= verbosity
+}
+
+const fn label(level: LogLevel) -> &'static str {
     match level {
-        LogLevel::Error
-        // | LogLevel::Warning
-        => eprintln!("{message}"),
-        // LogLevel::Info => println!("{message}"),
+        LogLevel::Error => "error",
+        LogLevel::Warning => "warning",
+        LogLevel::Info => "info",
+        LogLevel::Debug => "debug",
+        LogLevel::Trace => "trace",
+    }
+}
+
+fn write(level: LogLevel, message: &str) {
+    let Some(settings) = SETTINGS.get() else {
+        return;
+    };
+
+    if let Some(log_file) = &settings.log_file
+        && let Ok(mut log_file) = log_file.lock()
+    {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        let _ = writeln!(log_file, "{timestamp} [{}] {message}", label(level));
+    }
+
+    if !is_shown(level, settings.verbosity) {
+        return;
+    }
+
+    let label = match level {
+        LogLevel::Error => label(level).bold().red(),
+        LogLevel::Warning => label(level).bold().yellow(),
+        LogLevel::Info => label(level).bold().green(),
+        LogLevel::Debug => label(level).bold().blue(),
+        LogLevel::Trace => label(level).dimmed(),
+    };
+
+    eprintln!("{label}: {message}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_shows_info_and_above_by_default() {
+        let verbosity = get_verbosity(0, 0);
+
+        assert!(is_shown(LogLevel::Info, verbosity));
+        assert!(!is_shown(LogLevel::Debug, verbosity));
+    }
+
+    #[test]
+    fn it_raises_and_lowers_the_level_by_one_per_flag() {
+        assert!(is_shown(LogLevel::Debug, get_verbosity(1, 0)));
+        assert!(!is_shown(LogLevel::Trace, get_verbosity(1, 0)));
+        assert!(is_shown(LogLevel::Trace, get_verbosity(2, 0)));
+
+        assert!(is_shown(LogLevel::Warning, get_verbosity(0, 1)));
+        assert!(!is_shown(LogLevel::Info, get_verbosity(0, 1)));
+        assert!(!is_shown(LogLevel::Error, get_verbosity(0, 3)));
+        assert!(!is_shown(LogLevel::Error, get_verbosity(0, u8::MAX)));
     }
 }
@@ -1,17 +1,45 @@
 mod commands;
+mod error;
+mod git;
 mod log;
 mod repo;
 
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use ::repo::clone::CloneConfig;
+use ::repo::config::{get_config, select_profile};
+use ::repo::forge::RemoteRepoFilter;
+use clap::{ArgAction, Parser, Subcommand};
 use commands::{
-    add::add,
+    add::{CloneFilterOption, VisibilityOption, add},
+    archive::{archive, unarchive},
+    backup::{backup, restore_backup},
     cd::cd,
-    config::{config, edit_config, get_config_value},
+    config::{config, edit_config, get_config_value, profiles},
+    doctor::doctor,
+    exec::exec,
+    export::{ManifestFormat, export},
     hook::hook,
+    import::{ImportSource, import},
     list::list,
-    list::{SortByOption, hosts, list_all, list_unmanaged, names, owners},
+    list::{
+        SortByOption, hosts, list_all, list_archived, list_unmanaged, names,
+        owners,
+    },
+    mirror::{gc_mirrors, update_mirrors},
+    relayout::relayout,
+    relocate::relocate,
     remove::remove,
+    rename::{move_host, rename_owner},
+    status::status,
+    sync::sync,
+    tag::{add_tags, list_tags, remove_tags},
+    unshallow::unshallow,
+    worktree::{add_worktree, list_worktrees, remove_worktree},
 };
+use error::{Status, exit_status};
+use log::{ColorChoice, LogLevel, log};
 
 /// Manage source code repositories
 #[derive(Parser)]
@@ -19,6 +47,26 @@ use commands::{
 struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
+
+    /// Show more log messages (repeat for more detail)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Show fewer log messages (repeat to silence errors too)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    quiet: u8,
+
+    /// When to use color in log messages (`auto` respects `NO_COLOR`)
+    #[arg(long, value_enum, default_value_t, global = true)]
+    color: ColorChoice,
+
+    /// Append every log message to this file
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+
+    /// Use this config profile (overrides `SRC_PROFILE`)
+    #[arg(long, global = true)]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -28,6 +76,9 @@ enum ConfigSubcommand {
 
     /// Get a config value
     Get { key: String },
+
+    /// List config profiles
+    Profiles,
 }
 
 #[derive(Subcommand)]
@@ -54,6 +105,10 @@ enum ListSubcommand {
         #[arg(long)]
         name: Option<String>,
 
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
         /// (Not compatible with `--path`) Don't display host values
         #[arg(long)]
         no_host: bool,
@@ -114,6 +169,10 @@ enum ListSubcommand {
         #[arg(long)]
         name: Option<String>,
 
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
         /// (Not compatible with `--path`) Don't display host values
         #[arg(long)]
         no_host: bool,
@@ -142,12 +201,94 @@ enum ListSubcommand {
     },
 }
 
+#[derive(Subcommand)]
+enum MirrorSubcommand {
+    /// Repack the mirrors (without pruning objects clones may borrow)
+    Gc {
+        /// Also delete mirrors that no managed repository borrows from
+        #[arg(long)]
+        unused: bool,
+    },
+
+    /// Fetch every mirror from its remote
+    Update,
+}
+
+#[derive(Subcommand)]
+enum TagSubcommand {
+    /// Add tags to a repository
+    Add {
+        repo: String,
+
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+
+    /// Show the tags of a repository, or all tags if no repository is given
+    List { repo: Option<String> },
+
+    /// Remove tags from a repository
+    Remove {
+        repo: String,
+
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorktreeSubcommand {
+    /// Check out a branch of a repository in a new worktree, at
+    /// `<repo>.worktrees/<branch>`
+    Add {
+        repo: String,
+        branch: String,
+
+        /// Create the branch
+        #[arg(long, short = 'b')]
+        new: bool,
+
+        /// Look for the repository in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+    },
+
+    /// Show the worktrees of a repository, or of every repository if none is
+    /// given
+    List {
+        repo: Option<String>,
+
+        /// Look for repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+    },
+
+    /// Remove the worktree of a branch of a repository
+    Remove {
+        repo: String,
+        branch: String,
+
+        /// Remove the worktree even if it has uncommitted changes
+        #[arg(long)]
+        force: bool,
+
+        /// Look for the repository in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum Command {
     /// Add local or clone remote repositories
     Add {
         repos: Vec<String>,
 
+        /// Add to the root directory with this name instead of the one
+        /// chosen by the config rules
+        #[arg(long)]
+        root: Option<String>,
+
         /// Filter to repositories with host partially matching this value
         #[arg(long)]
         host: Option<String>,
@@ -160,6 +301,61 @@ enum Command {
         #[arg(long)]
         me: bool,
 
+        /// Add every repository of this user or organization on the forge at
+        /// `--host` (or the default host)
+        #[arg(long, conflicts_with = "repos")]
+        all_from: Option<String>,
+
+        /// With `--all-from`, include archived repositories
+        #[arg(long, requires = "all_from")]
+        archived: bool,
+
+        /// With `--all-from`, include forks
+        #[arg(long, requires = "all_from")]
+        forks: bool,
+
+        /// With `--all-from`, only add repositories with this visibility
+        #[arg(long, value_enum, default_value_t, requires = "all_from")]
+        visibility: VisibilityOption,
+
+        /// Clone only this many commits of history
+        #[arg(long)]
+        depth: Option<u32>,
+
+        /// Make a partial clone, fetching missing objects on demand
+        #[arg(long, value_enum)]
+        filter: Option<CloneFilterOption>,
+
+        /// Clone only the history of one branch
+        #[arg(long)]
+        single_branch: bool,
+
+        /// Check out this branch instead of the remote's default branch
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Clone submodules, even if `submodules` is off in the config
+        #[arg(long)]
+        recurse_submodules: bool,
+
+        /// Don't clone submodules
+        #[arg(long, conflicts_with = "recurse_submodules")]
+        no_recurse_submodules: bool,
+
+        /// Add an `upstream` remote pointing to this repository
+        #[arg(long, value_name = "REPO", conflicts_with = "all_from")]
+        upstream: Option<String>,
+
+        /// Add an `upstream` remote pointing to the repository the forge
+        /// says each fork was forked from
+        #[arg(long, conflicts_with = "upstream")]
+        detect_upstream: bool,
+
+        /// Clone through a local mirror, even if `mirror` is not set in the
+        /// config
+        #[arg(long)]
+        mirror: bool,
+
         // TODO
         // #[arg(long)]
         // cd: bool,
@@ -170,6 +366,34 @@ enum Command {
         // open: bool,
     },
 
+    /// Pack repositories, working tree and all, into the archive directory
+    /// and remove them from their root
+    Archive {
+        #[arg(required = true)]
+        repos: Vec<String>,
+
+        /// Look for repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+    },
+
+    /// Write a git bundle of every ref of repositories to a directory, with
+    /// an index of their names and remotes
+    Backup {
+        directory: String,
+
+        /// Repositories to back up (all if none are given)
+        repos: Vec<String>,
+
+        /// Filter to repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
     /// Open the remote repository web page in the browser
     Browse,
 
@@ -183,10 +407,22 @@ enum Command {
         #[arg(long)]
         name: Option<String>,
 
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
         /// Filter to repositories with owner partially matching this value
         #[arg(long)]
         owner: Option<String>,
 
+        /// Filter to repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Change to the worktree of this branch instead
+        #[arg(long)]
+        worktree: Option<String>,
+
         /// Repository name
         repo: Option<String>,
     },
@@ -197,14 +433,109 @@ enum Command {
         command: Option<ConfigSubcommand>,
     },
 
+    /// Check the managed tree for stray, empty, broken, misplaced and
+    /// duplicate repositories
+    Doctor {
+        /// Only check the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Offer to repair the problems that can be repaired safely
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Run a command in each selected repository
+    Exec {
+        /// Filter to repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Filter to repositories with host partially matching this value
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Filter to repositories with owner partially matching this value
+        #[arg(long)]
+        owner: Option<String>,
+
+        /// Filter to repositories with owner matching the value of config.username
+        #[arg(long)]
+        me: bool,
+
+        /// Filter to repositories with name partially matching this value
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Run in this many repositories at once
+        #[arg(long, short, default_value_t = 1)]
+        jobs: usize,
+
+        /// Prefix each line of output with its repository instead of
+        /// grouping the output under a heading
+        #[arg(long)]
+        prefix: bool,
+
+        /// The command to run, after `--`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Write a manifest of managed repositories
+    Export {
+        /// Only include repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Only include repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Manifest format (defaults to the extension of `--output`, or TOML)
+        #[arg(long, value_enum)]
+        format: Option<ManifestFormat>,
+
+        /// Write the manifest to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
     /// Generate shell hook (required for `cd`)
     Hook,
 
+    /// Add the repositories from a manifest or another repository manager
+    Import {
+        /// Manifest written by `export` (JSON if it ends in `.json`, TOML
+        /// otherwise), or the file or directory to read with `--from`
+        source: Option<PathBuf>,
+
+        /// What to import from
+        #[arg(long, value_enum, default_value_t)]
+        from: ImportSource,
+
+        /// Add to the root directory with this name instead of the one
+        /// chosen by the config rules
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Show what would be cloned without cloning anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// List repositories
     List {
         #[command(subcommand)]
         command: Option<ListSubcommand>,
 
+        /// Filter to repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
         /// Filter to repositories with host partially matching this value
         #[arg(long)]
         host: Option<String>,
@@ -221,6 +552,10 @@ enum Command {
         #[arg(long)]
         name: Option<String>,
 
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
         /// (Not compatible with `--path`) Don't display host values
         #[arg(long)]
         no_host: bool,
@@ -229,21 +564,92 @@ enum Command {
         #[arg(long)]
         no_owner: bool,
 
+        /// (Not compatible with `--path`) Display hosts by their alias from
+        /// `[aliases]`
+        #[arg(long)]
+        aliases: bool,
+
         /// List as paths
         #[arg(long)]
         path: bool,
 
+        /// Also list the worktrees of each repository
+        #[arg(long)]
+        worktrees: bool,
+
+        /// Also list the submodules of each repository
+        #[arg(long)]
+        submodules: bool,
+
+        /// List archived repositories instead (with `--path`, as the paths
+        /// of their archives)
+        #[arg(long, conflicts_with_all = ["root", "worktrees", "submodules"])]
+        archived: bool,
+
         #[arg(long)]
         sort_by: Option<SortByOption>,
     },
 
+    /// Manage the local mirrors that repositories are cloned through
+    Mirror {
+        #[command(subcommand)]
+        command: MirrorSubcommand,
+    },
+
+    /// Point repositories at a new host and move them to match, as when
+    /// moving from github.com to a self-hosted forge
+    MoveHost {
+        from: String,
+        to: String,
+
+        /// Only update repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Show what would be changed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Create or initialize a new repository
     New { path: Option<String> },
 
+    /// Move managed repositories to where the configured `layout` places
+    /// them
+    Relayout {
+        /// Only move repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Show what would be moved without moving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Move repositories whose remote has changed to where it places them,
+    /// recording each move in `relocations.log` in the data directory
+    Relocate {
+        /// Repositories to check, by path or by the name they are kept
+        /// under (all of them if none are given)
+        repos: Vec<String>,
+
+        /// Only check repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Show what would be moved without moving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Remove repositories
     Remove {
         repos: Vec<String>,
 
+        /// Filter to repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
         /// Filter to repositories with host partially matching this value
         #[arg(long)]
         host: Option<String>,
@@ -256,40 +662,200 @@ enum Command {
         #[arg(long)]
         me: bool,
 
+        /// Filter to repositories with this tag (selects every tagged
+        /// repository if no repositories are given)
+        #[arg(long)]
+        tag: Option<String>,
+
         #[arg(long)]
         force: bool,
     },
 
-    /// Sync repositories
-    Sync { repos: Vec<String> },
+    /// Point repositories of a renamed owner, and of the namespaces nested
+    /// in it, at the new owner and move them to match
+    RenameOwner {
+        from: String,
+        to: String,
+
+        /// Only rename the owner on this host
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Only update repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Show what would be changed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Recreate the repositories in a directory written by `backup`, with
+    /// their remotes configured as they were
+    RestoreBackup {
+        directory: String,
+
+        /// Restore to the root directory with this name instead of the one
+        /// each repository was backed up from
+        #[arg(long)]
+        root: Option<String>,
+    },
+
+    /// Show the branch and state of repositories and their submodules
+    Status {
+        /// Repositories to show (all if none are given)
+        repos: Vec<String>,
+
+        /// Filter to repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
+    /// Fetch repositories and fast-forward their current branch
+    Sync {
+        /// Repositories to sync (all if none are given)
+        repos: Vec<String>,
+
+        /// Filter to repositories in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Filter to repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
+    /// Manage repository tags
+    Tag {
+        #[command(subcommand)]
+        command: TagSubcommand,
+    },
+
+    /// Restore archived repositories to where they were archived from
+    Unarchive {
+        #[arg(required = true)]
+        repos: Vec<String>,
+    },
+
+    /// Fetch the history and objects missing from a shallow, single-branch
+    /// or partial clone
+    Unshallow {
+        repo: String,
+
+        /// Look for the repository in the root directory with this name
+        #[arg(long)]
+        root: Option<String>,
+    },
+
+    /// Manage the worktrees of repositories
+    Worktree {
+        #[command(subcommand)]
+        command: WorktreeSubcommand,
+    },
 }
 
-fn main() {
-    let result = match &Cli::parse().command {
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    if let Some(profile) = &cli.profile {
+        select_profile(profile);
+    }
+
+    let log_file = cli
+        .log_file
+        .clone()
+        .or_else(|| get_config().ok().and_then(|config| config.log_file));
+
+    if let Err(error) =
+        log::init(cli.verbose, cli.quiet, cli.color, log_file.as_deref())
+    {
+        eprintln!("failed to open log file: {error}");
+
+        return Status::Usage.into();
+    }
+
+    let result = match &cli.command {
         Some(Command::Add {
             repos,
+            root,
             host,
             owner,
             me,
+            all_from,
+            archived,
+            forks,
+            visibility,
+            depth,
+            filter,
+            single_branch,
+            branch,
+            recurse_submodules,
+            no_recurse_submodules,
+            upstream,
+            detect_upstream,
+            mirror,
             force,
-        }) => add(repos, host.as_ref(), owner.as_ref(), *me, *force),
+        }) => add(
+            repos,
+            root.as_ref(),
+            host.as_ref(),
+            owner.as_ref(),
+            *me,
+            all_from.as_ref(),
+            RemoteRepoFilter {
+                archived: *archived,
+                forks: *forks,
+                visibility: (*visibility).into(),
+            },
+            CloneConfig {
+                depth: *depth,
+                filter: filter.map(Into::into),
+                single_branch: single_branch.then_some(true),
+                branch: branch.clone(),
+                recurse_submodules: if *recurse_submodules {
+                    Some(true)
+                } else if *no_recurse_submodules {
+                    Some(false)
+                } else {
+                    None
+                },
+            },
+            upstream.as_ref(),
+            *detect_upstream,
+            *mirror,
+            *force,
+        ),
+
+        Some(Command::Archive { repos, root }) => {
+            archive(repos, root.as_ref())
+        }
+
+        Some(Command::Backup {
+            directory,
+            repos,
+            root,
+            tag,
+        }) => backup(directory, repos, root.as_ref(), tag.as_ref()),
 
         Some(Command::Browse) => {
-            println!("Implement me!");
+            log(&LogLevel::Warning, "`browse` is not implemented yet");
 
             Ok(())
         }
 
         Some(Command::Cd {
+            root: _,
             repo: _,
             host: _,
             name: _,
             owner: _,
-        }) => {
-            cd();
-
-            Ok(())
-        }
+            tag: _,
+            worktree: _,
+        }) => cd(),
 
         Some(Command::Config { command }) => {
             command
@@ -297,34 +863,95 @@ fn main() {
                 .map_or_else(config, |command| match command {
                     ConfigSubcommand::Edit => edit_config(),
                     ConfigSubcommand::Get { key } => get_config_value(key),
+                    ConfigSubcommand::Profiles => profiles(),
                 })
         }
 
+        Some(Command::Doctor { root, fix }) => doctor(root.as_ref(), *fix),
+
+        Some(Command::Exec {
+            root,
+            host,
+            owner,
+            me,
+            name,
+            tag,
+            jobs,
+            prefix,
+            command,
+        }) => exec(
+            root.as_ref(),
+            host.as_ref(),
+            owner.as_ref(),
+            name.as_ref(),
+            tag.as_ref(),
+            *me,
+            *jobs,
+            *prefix,
+            command,
+        ),
+
+        Some(Command::Export {
+            root,
+            tag,
+            format,
+            output,
+        }) => export(root.as_ref(), tag.as_ref(), *format, output.as_ref()),
+
         Some(Command::Hook) => {
             hook();
 
             Ok(())
         }
 
+        Some(Command::Import {
+            source,
+            from,
+            root,
+            dry_run,
+        }) => import(source.as_ref(), *from, root.as_ref(), *dry_run),
+
         Some(Command::List {
             command,
+            root,
             host,
             owner,
             me,
             name,
+            tag,
             no_host,
             no_owner,
+            aliases,
             path,
+            worktrees,
+            submodules,
+            archived,
             sort_by,
         }) => match command {
+            None if *archived => list_archived(
+                host.as_ref(),
+                owner.as_ref(),
+                name.as_ref(),
+                tag.as_ref(),
+                *me,
+                *no_host,
+                *no_owner,
+                *path,
+            ),
+
             None => list(
+                root.as_ref(),
                 host.as_ref(),
                 owner.as_ref(),
                 name.as_ref(),
+                tag.as_ref(),
                 *me,
                 *no_host,
                 *no_owner,
+                *aliases,
                 *path,
+                *worktrees,
+                *submodules,
                 sort_by.as_ref(),
             ),
 
@@ -334,6 +961,7 @@ fn main() {
                 owner: all_owner,
                 me: all_me,
                 name: all_name,
+                tag: all_tag,
                 no_host: all_no_host,
                 no_owner: all_no_owner,
                 path: all_path,
@@ -342,6 +970,7 @@ fn main() {
                 let host = all_host.as_ref().map_or(host, |_| all_host);
                 let me = *all_me || *me;
                 let name = all_name.clone().map_or(name, |_| all_name);
+                let tag = all_tag.clone().map_or(tag, |_| all_tag);
                 let no_host = *all_no_host || *no_host;
                 let no_owner = *all_no_owner || *no_owner;
                 let owner = all_owner.clone().map_or(owner, |_| all_owner);
@@ -354,6 +983,7 @@ fn main() {
                     host.as_ref(),
                     owner.as_ref(),
                     name.as_ref(),
+                    tag.as_ref(),
                     me,
                     no_host,
                     no_owner,
@@ -376,6 +1006,7 @@ fn main() {
                 owner: all_owner,
                 me: all_me,
                 name: all_name,
+                tag: all_tag,
                 no_host: all_no_host,
                 no_owner: all_no_owner,
                 path: all_path,
@@ -384,6 +1015,7 @@ fn main() {
                 let host = all_host.as_ref().map_or(host, |_| all_host);
                 let me = *all_me || *me;
                 let name = all_name.clone().map_or(name, |_| all_name);
+                let tag = all_tag.clone().map_or(tag, |_| all_tag);
                 let no_host = *all_no_host || *no_host;
                 let no_owner = *all_no_owner || *no_owner;
                 let owner = all_owner.clone().map_or(owner, |_| all_owner);
@@ -396,6 +1028,7 @@ fn main() {
                     host.as_ref(),
                     owner.as_ref(),
                     name.as_ref(),
+                    tag.as_ref(),
                     me,
                     no_host,
                     no_owner,
@@ -409,31 +1042,114 @@ fn main() {
             }
         },
 
+        Some(Command::MoveHost {
+            from,
+            to,
+            root,
+            dry_run,
+        }) => move_host(from, to, root.as_ref(), *dry_run),
+
         Some(Command::New { path: _ }) => {
-            eprintln!("Implement new!");
+            log(&LogLevel::Warning, "`new` is not implemented yet");
 
             Ok(())
         }
 
+        Some(Command::Relayout { root, dry_run }) => {
+            relayout(root.as_ref(), *dry_run)
+        }
+
+        Some(Command::Relocate {
+            repos,
+            root,
+            dry_run,
+        }) => relocate(repos, root.as_ref(), *dry_run),
+
         Some(Command::Remove {
             repos,
+            root,
             host,
             owner,
             me,
+            tag,
             force,
-        }) => remove(repos, host.as_ref(), owner.as_ref(), *me, *force),
+        }) => remove(
+            repos,
+            root.as_ref(),
+            host.as_ref(),
+            owner.as_ref(),
+            *me,
+            tag.as_ref(),
+            *force,
+        ),
+
+        Some(Command::RenameOwner {
+            from,
+            to,
+            host,
+            root,
+            dry_run,
+        }) => rename_owner(from, to, host.as_ref(), root.as_ref(), *dry_run),
 
-        Some(Command::Sync { repos: _ }) => {
-            eprintln!("Implement sync!");
+        Some(Command::RestoreBackup { directory, root }) => {
+            restore_backup(directory, root.as_ref())
+        }
 
-            Ok(())
+        Some(Command::Status { repos, root, tag }) => {
+            status(repos, root.as_ref(), tag.as_ref())
         }
 
+        Some(Command::Sync { repos, root, tag }) => {
+            sync(repos, root.as_ref(), tag.as_ref())
+        }
+
+        Some(Command::Mirror { command }) => match command {
+            MirrorSubcommand::Gc { unused } => gc_mirrors(*unused),
+            MirrorSubcommand::Update => update_mirrors(),
+        },
+
+        Some(Command::Tag { command }) => match command {
+            TagSubcommand::Add { repo, tags } => add_tags(repo, tags),
+            TagSubcommand::List { repo } => list_tags(repo.as_ref()),
+            TagSubcommand::Remove { repo, tags } => remove_tags(repo, tags),
+        },
+
+        Some(Command::Unarchive { repos }) => unarchive(repos),
+
+        Some(Command::Unshallow { repo, root }) => {
+            unshallow(repo, root.as_ref())
+        }
+
+        Some(Command::Worktree { command }) => match command {
+            WorktreeSubcommand::Add {
+                repo,
+                branch,
+                new,
+                root,
+            } => add_worktree(repo, branch, *new, root.as_ref()),
+
+            WorktreeSubcommand::List { repo, root } => {
+                list_worktrees(repo.as_ref(), root.as_ref())
+            }
+
+            WorktreeSubcommand::Remove {
+                repo,
+                branch,
+                force,
+                root,
+            } => remove_worktree(repo, branch, *force, root.as_ref()),
+        },
+
         None => Ok(()),
     };
 
     match result {
-        Ok(()) => (),
-        Err(error) => eprintln!("{error}"),
+        Ok(()) => Status::Success.into(),
+
+        Err(error) => {
+            log(&LogLevel::Error, &error.to_string());
+
+            exit_status(&error).into()
+        }
     }
 }
@@ -1,15 +1,19 @@
 use std::collections::HashSet;
+use std::path::PathBuf;
 
 use anyhow::Result;
 use repo::{
-    config::{Config, get_root_directory},
+    config::Config,
     error::SrcRepoError,
+    list::get_repos,
     repo::{Repo, parse_repos},
+    root::{Root, find_managed_path},
+    tag::filter_by_tag,
 };
 
 use crate::{
     commands::list::{get_host_names, get_owner_names},
-    log::{LogLevel, log},
+    error::{Failures, SrcError},
 };
 
 type GetValues = fn(bool, bool) -> Result<Vec<String>>;
@@ -37,12 +41,25 @@ fn get_values(
     Ok(values)
 }
 
+/// Parse `repos`, filtered by `host` and `owner`, keeping only the managed
+/// ones if `must_exist`.
+///
+/// With `failures`, each argument that can't be parsed is recorded there as a
+/// usage error, so that the others can still be used. Without, the first one
+/// is returned.
+///
+/// # Errors
+///
+/// Will return an error if the hosts or owners can't be listed, or, without
+/// `failures`, `SrcError::Usage` if an argument can't be parsed
 pub fn parse_repos_with_error_log(
     config: &Config,
+    roots: &[Root],
     repos: &[String],
     host: Option<&String>,
     owner: Option<&String>,
     must_exist: bool,
+    mut failures: Option<&mut Failures>,
 ) -> Result<Vec<Repo>> {
     // TODO: allow repos to be null and select all matching a host and/or owner
 
@@ -51,52 +68,189 @@ pub fn parse_repos_with_error_log(
     let default_host = config.host.as_deref();
     let default_owner = config.owner.as_deref();
 
-    let mut matching_repos: Vec<Result<Repo, SrcRepoError>> = vec![];
+    let mut matching_repos: Vec<(&String, Result<Repo, SrcRepoError>)> =
+        vec![];
 
-    for host in hosts {
-        matching_repos.extend(parse_repos(
-            repos,
-            default_host,
-            default_owner,
-            host.as_ref(),
-            None,
-        ));
+    // Each combination of host and owner, so that `--owner` filters by owner
+    // rather than being taken for a host.
+    for host in &hosts {
+        for owner in &owners {
+            matching_repos.extend(repos.iter().zip(parse_repos(
+                repos,
+                default_host,
+                default_owner,
+                host.as_ref(),
+                owner.as_ref(),
+                &config.remote_names,
+                &config.aliases,
+            )));
+        }
     }
 
-    for owner in owners {
-        matching_repos.extend(parse_repos(
-            repos,
-            default_host,
-            default_owner,
-            owner.as_ref(),
-            None,
-        ));
-    }
+    let mut parsed_repos = HashSet::new();
+    let mut unparsable_args = HashSet::new();
 
-    Ok(matching_repos
-        .into_iter()
-        .filter_map(|repo| match repo {
+    for (arg, repo) in matching_repos {
+        match repo {
             Ok(repo) => {
-                if must_exist {
-                    get_root_directory().map_or(None, |root_directory| {
-                        if repo.managed_path(&root_directory).exists() {
-                            Some(repo)
-                        } else {
-                            None
-                        }
-                    })
-                } else {
-                    Some(repo)
+                if !must_exist || find_managed_path(roots, &repo).is_some() {
+                    parsed_repos.insert(repo);
                 }
             }
 
+            // The repository doesn't match this host or owner.
+            Err(SrcRepoError::Filter) => {}
+
             Err(error) => {
-                log(&LogLevel::Error, &error.to_string());
+                let error = SrcError::Usage(format!("{error}: {arg}")).into();
+
+                let Some(failures) = failures.as_deref_mut() else {
+                    return Err(error);
+                };
 
-                None
+                // Once, however many hosts and owners it was parsed for.
+                if unparsable_args.insert(arg) {
+                    failures.record(Err(error));
+                }
             }
-        })
-        .collect::<HashSet<_>>()
+        }
+    }
+
+    Ok(parsed_repos.into_iter().collect())
+}
+
+/// Resolve `repo` to exactly one managed repository.
+///
+/// # Errors
+///
+/// Will return `SrcError::Usage` if no managed repository, or more than one,
+/// matches `repo`
+pub fn find_managed_repo(
+    config: &Config,
+    roots: &[Root],
+    repo: &str,
+) -> Result<Repo> {
+    let mut repos = parse_repos_with_error_log(
+        config,
+        roots,
+        &[repo.to_string()],
+        None,
+        None,
+        true,
+        None,
+    )?;
+
+    match repos.len() {
+        0 => Err(SrcError::Usage(format!(
+            "no managed repository matches {repo:?}"
+        ))
+        .into()),
+
+        1 => Ok(repos.remove(0)),
+
+        _ => {
+            let mut matches: Vec<String> =
+                repos.iter().map(ToString::to_string).collect();
+
+            matches.sort();
+
+            Err(SrcError::Usage(format!(
+                "{repo:?} matches more than one repository: {}",
+                matches.join(", ")
+            ))
+            .into())
+        }
+    }
+}
+
+/// Find the managed repository matching `repo`, and its path.
+///
+/// # Errors
+///
+/// Will return `SrcError::Usage` if no managed repository, or more than one,
+/// matches `repo`
+pub fn find_managed_repo_path(
+    config: &Config,
+    roots: &[Root],
+    repo: &str,
+) -> Result<(Repo, PathBuf)> {
+    let repo = find_managed_repo(config, roots, repo)?;
+
+    let path = find_managed_path(roots, &repo)
+        .ok_or_else(|| SrcError::Usage(format!("{repo} is not managed")))?;
+
+    Ok((repo, path))
+}
+
+/// The managed repositories matching `repos` (or all of them, if none are
+/// given) and `tag`, with their paths, in order. Arguments that can't be
+/// parsed are recorded in `failures`.
+///
+/// # Errors
+///
+/// Will return an error if the managed repositories can't be read, or the
+/// metadata can't be read when filtering by `tag`
+pub fn select_managed_repos(
+    config: &Config,
+    roots: &[Root],
+    repos: &[String],
+    tag: Option<&String>,
+    failures: &mut Failures,
+) -> Result<Vec<(Repo, PathBuf)>> {
+    let repos = if repos.is_empty() {
+        get_repos(config, roots, false, false)?
+    } else {
+        parse_repos_with_error_log(
+            config,
+            roots,
+            repos,
+            None,
+            None,
+            true,
+            Some(failures),
+        )?
+    };
+
+    let mut repos: Vec<(Repo, PathBuf)> = filter_by_tag(config, repos, tag)?
         .into_iter()
-        .collect())
+        .filter_map(|repo| {
+            let path = find_managed_path(roots, &repo)?;
+
+            Some((repo, path))
+        })
+        .collect();
+
+    repos.sort_by_key(|(repo, _)| repo.to_string());
+
+    Ok(repos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_takes_the_owner_given_for_an_owner() {
+        let config = Config {
+            host: Some("github.com".to_string()),
+            owner: Some("me".to_string()),
+            ..Config::default()
+        };
+
+        let repos = parse_repos_with_error_log(
+            &config,
+            &[],
+            &["tool".to_string()],
+            None,
+            Some(&"other".to_string()),
+            false,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].host, "github.com");
+        assert_eq!(repos[0].owner, "other");
+        assert_eq!(repos[0].name, "tool");
+    }
 }
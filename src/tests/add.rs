@@ -0,0 +1,72 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn it_replaces_a_managed_repo_with_a_local_one_when_forced() {
+    let sandbox = Sandbox::new("");
+    let url = sandbox.remote("github.com", "me/tool");
+    let managed = sandbox.clone(&url, "src/github.com/me/tool");
+    let local = sandbox.clone(&url, "elsewhere/tool");
+
+    std::fs::write(local.join("local.txt"), "local").unwrap();
+
+    sandbox
+        .src(&["add", &local.to_string_lossy()])
+        .assert()
+        .code(2);
+
+    assert!(local.exists());
+    assert!(!managed.join("local.txt").exists());
+
+    sandbox
+        .src(&["add", "--force", &local.to_string_lossy()])
+        .assert()
+        .success();
+
+    assert!(!local.exists());
+    assert!(managed.join("local.txt").exists());
+    assert!(!managed.join("tool").exists());
+}
+
+#[test]
+fn it_exits_with_a_usage_error_for_unparsable_repos() {
+    let sandbox = Sandbox::new("");
+
+    sandbox.src(&["add", ":::"]).assert().code(2);
+}
+
+#[test]
+fn it_adds_the_parsable_repos_and_reports_the_others() {
+    let sandbox = Sandbox::new("");
+    let url = sandbox.remote("github.com", "me/tool");
+
+    let output = sandbox
+        .src(&["--color", "never", "add", ":::", &url])
+        .assert()
+        .code(4);
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+
+    assert_eq!(stderr.matches(":::").count(), 1);
+    assert!(stderr.contains("1 of 2 repositories failed"));
+    assert!(sandbox.path("src/github.com/me/tool/README.md").exists());
+}
+
+#[test]
+fn it_takes_a_bare_name_for_a_remote_repo_even_if_a_directory_matches() {
+    let sandbox = Sandbox::new("host = \"github.com\"\nowner = \"me\"\n");
+    let project = sandbox.path("project");
+
+    sandbox.remote("github.com", "me/tool");
+    std::fs::create_dir_all(project.join("tool")).unwrap();
+    sandbox.git(&project, &["init", "--quiet"]);
+
+    sandbox
+        .src(&["add", "tool"])
+        .current_dir(&project)
+        .assert()
+        .success();
+
+    assert!(project.join(".git").exists());
+    assert!(sandbox.path("src/github.com/me/tool/README.md").exists());
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn it_archives_repos_cloned_through_mirrors_without_the_mirror() {
+    let sandbox = Sandbox::new("mirror_directory = \"~/mirrors\"\n");
+    let url = sandbox.remote("github.com", "me/tool");
+    let path = sandbox.path("src/github.com/me/tool");
+
+    sandbox.src(&["add", "--mirror", &url]).assert().success();
+
+    assert!(sandbox.path("mirrors/github.com/me/tool.git").exists());
+    assert!(path.join(".git/objects/info/alternates").exists());
+
+    // Fetched after the mirror was updated, so kept only in the mirror.
+    let work = sandbox.path(".work/github.com/me/tool");
+
+    std::fs::write(work.join("CHANGELOG.md"), "new").unwrap();
+    sandbox.git(&work, &["add", "CHANGELOG.md"]);
+    sandbox.git(&work, &["commit", "--quiet", "--message", "second"]);
+    sandbox.git(&work, &["push", "--quiet", &url, "main"]);
+    sandbox.src(&["mirror", "update"]).assert().success();
+    sandbox.git(&path, &["pull", "--quiet", "--ff-only"]);
+
+    sandbox.src(&["archive", "me/tool"]).assert().success();
+    sandbox
+        .src(&["mirror", "gc", "--unused"])
+        .assert()
+        .success();
+
+    assert!(!sandbox.path("mirrors/github.com/me/tool.git").exists());
+
+    sandbox.src(&["unarchive", "me/tool"]).assert().success();
+
+    sandbox.git(&path, &["fsck", "--full"]);
+    assert_eq!(sandbox.git(&path, &["log", "--format=%s"]), "second\nfirst");
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::fs;
+
+use common::Sandbox;
+
+#[test]
+fn it_restores_repos_to_the_root_they_were_backed_up_from() {
+    let sandbox = Sandbox::new("");
+    let home = sandbox.home.display().to_string();
+
+    fs::write(
+        sandbox.path(".config/src/config.toml"),
+        format!("[roots]\na = \"{home}/a\"\nb = \"{home}/b\"\n"),
+    )
+    .unwrap();
+
+    let url = sandbox.remote("github.com", "me/tool");
+    let path = sandbox.clone(&url, "b/github.com/me/tool");
+    let backup = sandbox.path("backup").to_string_lossy().to_string();
+
+    sandbox.src(&["backup", &backup]).assert().success();
+    fs::remove_dir_all(&path).unwrap();
+    sandbox.src(&["restore-backup", &backup]).assert().success();
+
+    assert!(path.join("README.md").exists());
+    assert!(!sandbox.path("a/github.com/me/tool").exists());
+
+    fs::remove_dir_all(&path).unwrap();
+    sandbox
+        .src(&["restore-backup", "--root", "a", &backup])
+        .assert()
+        .success();
+
+    assert!(sandbox.path("a/github.com/me/tool/README.md").exists());
+    assert!(!path.exists());
+}
+
+#[test]
+fn it_backs_up_to_and_restores_from_a_relative_directory() {
+    let sandbox = Sandbox::new("");
+    let url = sandbox.remote("github.com", "me/tool");
+    let path = sandbox.clone(&url, "src/github.com/me/tool");
+
+    sandbox.src(&["backup", "backup"]).assert().success();
+
+    assert!(sandbox.path("backup/github.com/me/tool.bundle").exists());
+
+    fs::remove_dir_all(&path).unwrap();
+    sandbox.src(&["restore-backup", "backup"]).assert().success();
+
+    assert!(path.join("README.md").exists());
+}
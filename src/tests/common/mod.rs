@@ -0,0 +1,164 @@
+//! A throwaway home directory to run `src` in, with its own config, and bare
+//! remotes that `git@<host>:` urls are served from.
+
+// Each test crate uses only some of this, and it only runs in tests.
+#![allow(dead_code, clippy::unwrap_used)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, process};
+
+static SANDBOXES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Sandbox {
+    pub home: PathBuf,
+}
+
+impl Sandbox {
+    /// A sandbox with `config` as its config file, and `root_directory` at
+    /// `~/src` unless `config` says otherwise.
+    pub fn new(config: &str) -> Self {
+        let home = env::temp_dir().join(format!(
+            "src-test-{}-{}",
+            process::id(),
+            SANDBOXES.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        let _ = fs::remove_dir_all(&home);
+
+        fs::create_dir_all(home.join(".config/src")).unwrap();
+        fs::create_dir_all(home.join("src")).unwrap();
+
+        let config = if config.contains("root_directory")
+            || config.contains("[roots]")
+        {
+            config.to_string()
+        } else {
+            format!(
+                "root_directory = \"{}\"\n{config}",
+                home.join("src").display()
+            )
+        };
+
+        fs::write(home.join(".config/src/config.toml"), config).unwrap();
+
+        // Serve `git@<host>:<path>` over a fake ssh from `remotes/<host>`, so
+        // that remotes keep urls that `src` can parse.
+        let ssh = home.join("ssh");
+
+        fs::write(
+            &ssh,
+            "#!/bin/sh\n\
+             for arg; do host=\"$command\"; command=\"$arg\"; done\n\
+             cd \"$HOME/remotes/${host#*@}\" && eval \"$command\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&ssh, fs::Permissions::from_mode(0o755)).unwrap();
+
+        fs::write(
+            home.join(".gitconfig"),
+            format!(
+                "[user]\nname = t\nemail = t@t\n[init]\ndefaultBranch = main\n\
+                 [core]\nsshCommand = {}\n",
+                ssh.display()
+            ),
+        )
+        .unwrap();
+
+        Self { home }
+    }
+
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.home.join(path)
+    }
+
+    fn env(&self, command: &mut Command) {
+        for (key, _) in env::vars() {
+            if key.starts_with("SRC_") || key.starts_with("GIT_") {
+                command.env_remove(key);
+            }
+        }
+
+        command
+            .env("HOME", &self.home)
+            .env("XDG_CONFIG_HOME", self.home.join(".config"))
+            .env("XDG_DATA_HOME", self.home.join(".local/share"))
+            .env("XDG_CACHE_HOME", self.home.join(".cache"))
+            .env("GIT_CONFIG_GLOBAL", self.home.join(".gitconfig"))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .current_dir(&self.home);
+    }
+
+    /// `src` with `args`, run in the sandbox.
+    pub fn src(&self, args: &[&str]) -> assert_cmd::Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_src"));
+
+        self.env(&mut command);
+        command.args(args);
+
+        assert_cmd::Command::from_std(command)
+    }
+
+    /// Run `git` with `args` in `directory`, returning its output.
+    pub fn git(&self, directory: &Path, args: &[&str]) -> String {
+        let mut command = Command::new("git");
+
+        self.env(&mut command);
+
+        let output =
+            command.current_dir(directory).args(args).output().unwrap();
+
+        assert!(
+            output.status.success(),
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Create a bare remote with one commit, reachable as
+    /// `git@<host>:<path>.git`, and return that url.
+    pub fn remote(&self, host: &str, path: &str) -> String {
+        let bare = self.path(&format!("remotes/{host}/{path}.git"));
+        let work = self.path(&format!(".work/{host}/{path}"));
+
+        fs::create_dir_all(&bare).unwrap();
+        fs::create_dir_all(&work).unwrap();
+
+        self.git(&bare, &["init", "--quiet", "--bare"]);
+        self.git(&work, &["init", "--quiet"]);
+        fs::write(work.join("README.md"), path).unwrap();
+        self.git(&work, &["add", "README.md"]);
+        self.git(&work, &["commit", "--quiet", "--message", "first"]);
+
+        let url = format!("git@{host}:{path}.git");
+
+        self.git(&work, &["push", "--quiet", &url, "main"]);
+
+        url
+    }
+
+    /// Clone `url` to `path` (relative to the home directory).
+    pub fn clone(&self, url: &str, path: &str) -> PathBuf {
+        let target = self.path(path);
+
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        self.git(
+            &self.home,
+            &["clone", "--quiet", url, &target.to_string_lossy()],
+        );
+
+        target
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn it_reports_each_failure_once() {
+    let sandbox = Sandbox::new("");
+
+    for name in ["fails", "works"] {
+        let url = sandbox.remote("github.com", &format!("me/{name}"));
+
+        sandbox.clone(&url, &format!("src/github.com/me/{name}"));
+    }
+
+    std::fs::write(sandbox.path("src/github.com/me/works/ok"), "").unwrap();
+
+    let output = sandbox
+        .src(&["--color", "never", "exec", "--", "test", "-e", "ok"])
+        .assert()
+        .code(4);
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+
+    assert_eq!(stderr.matches("github.com:me/fails").count(), 1);
+    assert!(!stderr.contains("github.com:me/works"));
+    assert!(stderr.contains("1 of 2 repositories failed"));
+}
+
+#[test]
+fn it_labels_output_with_the_repo_whatever_the_layout() {
+    let sandbox = Sandbox::new("layout = \"{name}\"\n");
+    let url = sandbox.remote("github.com", "me/tool");
+
+    sandbox.clone(&url, "src/tool");
+
+    let output = sandbox
+        .src(&["--color", "never", "exec", "--prefix", "--", "echo", "hi"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.get_output().stdout),
+        "github.com:me/tool: hi\n"
+    );
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::Sandbox;
+
+fn two_roots(sandbox_home: &str) -> String {
+    format!(
+        "[roots]\na = \"{sandbox_home}/a\"\nb = \"{sandbox_home}/b\"\n\
+         work = {{ path = \"{sandbox_home}/work\", hosts = \
+         [\"gitlab.example.com\"] }}\n"
+    )
+}
+
+#[test]
+fn it_moves_repos_to_where_their_new_remote_places_them() {
+    let sandbox = Sandbox::new("");
+    let url = sandbox.remote("github.com", "me/tool");
+    let path = sandbox.clone(&url, "src/github.com/me/tool");
+
+    sandbox.remote("github.com", "org/tool");
+    sandbox.git(
+        &path,
+        &["remote", "set-url", "origin", "git@github.com:org/tool.git"],
+    );
+
+    sandbox
+        .src(&["relocate", "--dry-run", &path.to_string_lossy()])
+        .assert()
+        .success();
+
+    assert!(path.exists());
+
+    sandbox
+        .src(&["relocate", &path.to_string_lossy()])
+        .assert()
+        .success();
+
+    assert!(!path.exists());
+    assert!(sandbox.path("src/github.com/org/tool/.git").exists());
+}
+
+#[test]
+fn it_keeps_repos_in_their_root_unless_another_roots_rules_match() {
+    let sandbox = Sandbox::new("");
+    let home = sandbox.home.display().to_string();
+
+    std::fs::write(sandbox.path(".config/src/config.toml"), two_roots(&home))
+        .unwrap();
+
+    let url = sandbox.remote("github.com", "me/tool");
+    let path = sandbox.clone(&url, "b/github.com/me/tool");
+
+    sandbox.remote("github.com", "org/tool");
+    sandbox.git(
+        &path,
+        &["remote", "set-url", "origin", "git@github.com:org/tool.git"],
+    );
+
+    sandbox.src(&["relocate"]).assert().success();
+
+    assert!(sandbox.path("b/github.com/org/tool/.git").exists());
+    assert!(!sandbox.path("a/github.com/org/tool").exists());
+
+    sandbox.remote("gitlab.example.com", "team/tool");
+    sandbox.git(
+        &sandbox.path("b/github.com/org/tool"),
+        &[
+            "remote",
+            "set-url",
+            "origin",
+            "git@gitlab.example.com:team/tool.git",
+        ],
+    );
+
+    sandbox.src(&["relocate"]).assert().success();
+
+    assert!(
+        sandbox
+            .path("work/gitlab.example.com/team/tool/.git")
+            .exists()
+    );
+    assert!(!sandbox.path("b/github.com/org/tool").exists());
+}
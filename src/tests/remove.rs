@@ -0,0 +1,41 @@
+mod common;
+
+use common::Sandbox;
+
+fn two_owners(sandbox: &Sandbox) {
+    for owner in ["me", "other"] {
+        let url = sandbox.remote("github.com", &format!("{owner}/tool"));
+
+        sandbox.clone(&url, &format!("src/github.com/{owner}/tool"));
+    }
+}
+
+#[test]
+fn it_removes_only_the_repos_of_the_owner_given() {
+    let sandbox = Sandbox::new("host = \"github.com\"\nowner = \"me\"\n");
+
+    two_owners(&sandbox);
+
+    sandbox
+        .src(&["remove", "--force", "--owner", "other", "tool"])
+        .assert()
+        .success();
+
+    assert!(!sandbox.path("src/github.com/other/tool").exists());
+    assert!(sandbox.path("src/github.com/me/tool/.git").exists());
+}
+
+#[test]
+fn it_removes_only_the_tagged_repos_of_the_owner_given() {
+    let sandbox = Sandbox::new("[tags]\nwork = [\"github.com\"]\n");
+
+    two_owners(&sandbox);
+
+    sandbox
+        .src(&["remove", "--force", "--tag", "work", "--owner", "other"])
+        .assert()
+        .success();
+
+    assert!(!sandbox.path("src/github.com/other/tool").exists());
+    assert!(sandbox.path("src/github.com/me/tool/.git").exists());
+}
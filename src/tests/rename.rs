@@ -0,0 +1,72 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn it_keeps_renamed_repos_in_their_root() {
+    let sandbox = Sandbox::new("");
+    let home = sandbox.home.display().to_string();
+
+    std::fs::write(
+        sandbox.path(".config/src/config.toml"),
+        format!("[roots]\na = \"{home}/a\"\nb = \"{home}/b\"\n"),
+    )
+    .unwrap();
+
+    let url = sandbox.remote("github.com", "old/tool");
+    let path = sandbox.clone(&url, "b/github.com/old/tool");
+
+    sandbox
+        .src(&["rename-owner", "old", "new"])
+        .assert()
+        .success();
+
+    assert!(!path.exists());
+    assert!(!sandbox.path("a/github.com/new/tool").exists());
+
+    let path = sandbox.path("b/github.com/new/tool");
+
+    assert_eq!(
+        sandbox.git(&path, &["remote", "get-url", "origin"]),
+        "git@github.com:new/tool.git"
+    );
+}
+
+#[test]
+fn it_only_points_the_identifying_remote_of_renamed_repos() {
+    let sandbox = Sandbox::new("");
+    let renamed = sandbox.clone(
+        &sandbox.remote("github.com", "old/tool"),
+        "src/github.com/old/tool",
+    );
+    let kept = sandbox.clone(
+        &sandbox.remote("github.com", "other/lib"),
+        "src/github.com/other/lib",
+    );
+
+    for path in [&renamed, &kept] {
+        sandbox.git(
+            path,
+            &["remote", "add", "upstream", "git@github.com:old/base.git"],
+        );
+    }
+
+    sandbox
+        .src(&["rename-owner", "old", "new"])
+        .assert()
+        .success();
+
+    let renamed = sandbox.path("src/github.com/new/tool");
+
+    assert_eq!(
+        sandbox.git(&renamed, &["remote", "get-url", "origin"]),
+        "git@github.com:new/tool.git"
+    );
+
+    for path in [&renamed, &kept] {
+        assert_eq!(
+            sandbox.git(path, &["remote", "get-url", "upstream"]),
+            "git@github.com:old/base.git"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::fs;
+
+use common::Sandbox;
+
+#[test]
+fn it_syncs_from_the_configured_remote_and_skips_local_repos() {
+    let sandbox = Sandbox::new("remote_names = [\"github\"]\n");
+    let url = sandbox.remote("github.com", "me/tool");
+    let path = sandbox.path("src/github.com/me/tool");
+
+    sandbox.git(
+        &sandbox.home,
+        &[
+            "clone",
+            "--quiet",
+            "--origin",
+            "github",
+            &url,
+            "src/github.com/me/tool",
+        ],
+    );
+
+    let local = sandbox.path("src/local/scratch");
+
+    fs::create_dir_all(&local).unwrap();
+    sandbox.git(&local, &["init", "--quiet"]);
+
+    let work = sandbox.path(".work/github.com/me/tool");
+
+    fs::write(work.join("CHANGELOG.md"), "new").unwrap();
+    sandbox.git(&work, &["add", "CHANGELOG.md"]);
+    sandbox.git(&work, &["commit", "--quiet", "--message", "second"]);
+    sandbox.git(&work, &["push", "--quiet", &url, "main"]);
+
+    sandbox.src(&["sync"]).assert().success();
+
+    assert!(path.join("CHANGELOG.md").exists());
+}
+
+#[test]
+fn it_skips_repos_it_cant_identify() {
+    let sandbox = Sandbox::new("");
+    let url = sandbox.remote("github.com", "me/tool");
+    let path = sandbox.clone(&url, "src/github.com/me/tool");
+    let unknown = sandbox.clone(&url, "src/github.com/me/unknown");
+
+    sandbox.git(&unknown, &["remote", "rename", "origin", "a"]);
+    sandbox.git(&unknown, &["remote", "add", "b", &url]);
+
+    let work = sandbox.path(".work/github.com/me/tool");
+
+    fs::write(work.join("CHANGELOG.md"), "new").unwrap();
+    sandbox.git(&work, &["add", "CHANGELOG.md"]);
+    sandbox.git(&work, &["commit", "--quiet", "--message", "second"]);
+    sandbox.git(&work, &["push", "--quiet", &url, "main"]);
+
+    let output = sandbox.src(&["sync"]).assert().success();
+
+    assert!(path.join("CHANGELOG.md").exists());
+    assert!(
+        String::from_utf8_lossy(&output.get_output().stderr)
+            .contains("skipping")
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::fs;
+
+use common::Sandbox;
+
+fn partial_clone(sandbox: &Sandbox) -> std::path::PathBuf {
+    let url = sandbox.remote("github.com", "me/tool");
+
+    sandbox.git(
+        &sandbox.path("remotes/github.com/me/tool.git"),
+        &["config", "uploadpack.allowFilter", "true"],
+    );
+
+    sandbox
+        .src(&["add", "--filter", "blob:none", "--single-branch", &url])
+        .assert()
+        .success();
+
+    sandbox.path("src/github.com/me/tool")
+}
+
+#[test]
+fn it_turns_partial_clones_into_full_ones() {
+    let sandbox = Sandbox::new("");
+    let path = partial_clone(&sandbox);
+
+    sandbox.src(&["unshallow", "me/tool"]).assert().success();
+
+    assert_eq!(
+        sandbox.git(&path, &["config", "remote.origin.promisor"]),
+        "false"
+    );
+    assert_eq!(
+        sandbox.git(&path, &["config", "remote.origin.fetch"]),
+        "+refs/heads/*:refs/remotes/origin/*"
+    );
+    assert!(
+        !sandbox
+            .git(
+                &path,
+                &["rev-list", "--objects", "--missing=print", "--all"]
+            )
+            .contains('?')
+    );
+}
+
+#[test]
+fn it_leaves_the_clone_as_it_was_when_fetching_fails() {
+    let sandbox = Sandbox::new("");
+    let path = partial_clone(&sandbox);
+
+    fs::remove_dir_all(sandbox.path("remotes/github.com/me/tool.git"))
+        .unwrap();
+
+    sandbox.src(&["unshallow", "me/tool"]).assert().failure();
+
+    assert_eq!(
+        sandbox.git(&path, &["config", "remote.origin.promisor"]),
+        "true"
+    );
+    assert_eq!(
+        sandbox.git(&path, &["config", "remote.origin.partialclonefilter"]),
+        "blob:none"
+    );
+    assert_eq!(
+        sandbox.git(&path, &["config", "remote.origin.fetch"]),
+        "+refs/heads/main:refs/remotes/origin/main"
+    );
+}